@@ -0,0 +1,1560 @@
+//! The parts of `time_cli` that don't need a terminal: Wikipedia's On This
+//! Day feed (fetching, caching and the response models) and the day and
+//! year statistics behind `--statistics`. The binary is a thin CLI on top.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{
+    DateTime, Datelike, FixedOffset, Local, NaiveDate, TimeDelta, TimeZone,
+    Utc,
+};
+use chrono_tz::Tz;
+use clap::ValueEnum;
+use futures::{stream, StreamExt};
+use reqwest::header::{
+    ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::{Client, StatusCode, Url};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/* --------------------------------------------------------------------------
+ *                                models
+ * ---------------------------------------------------------------------- */
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[value(rename_all = "lower")]
+pub enum EventType {
+    Events,
+    Births,
+    Deaths,
+    Holidays,
+    /// A short, curated pick of the day's most notable events
+    Selected,
+    /// Every category above except `selected`, one section each
+    All,
+    /// Events followed by holidays, in a single list
+    Anniversaries,
+    /// Wikimedia Commons' picture of the day: title, description and
+    /// source page
+    Featured,
+}
+
+impl EventType {
+    /// The single categories this type is made of, in display order.
+    pub fn parts(self) -> &'static [EventType] {
+        match self {
+            EventType::Events => &[EventType::Events],
+            EventType::Births => &[EventType::Births],
+            EventType::Deaths => &[EventType::Deaths],
+            EventType::Holidays => &[EventType::Holidays],
+            EventType::Selected => &[EventType::Selected],
+            EventType::All => &[
+                EventType::Events,
+                EventType::Births,
+                EventType::Deaths,
+                EventType::Holidays,
+            ],
+            EventType::Anniversaries => {
+                &[EventType::Events, EventType::Holidays]
+            }
+            EventType::Featured => &[EventType::Featured],
+        }
+    }
+
+    /// Heading for this category's section in combined views.
+    pub fn title(self) -> &'static str {
+        match self {
+            EventType::Events => "Events",
+            EventType::Births => "Births",
+            EventType::Deaths => "Deaths",
+            EventType::Holidays => "Holidays",
+            EventType::Selected => "Selected",
+            EventType::All => "All",
+            EventType::Anniversaries => "Anniversaries",
+            EventType::Featured => "Featured picture",
+        }
+    }
+
+    /// The type's segment in the feed's URL, `/feed/onthisday/<segment>`
+    /// (`/feed/<segment>` for the featured picture). Anniversaries are cut
+    /// from the combined feed.
+    pub fn api_segment(self) -> &'static str {
+        match self {
+            EventType::Anniversaries => "all",
+            other => other.name(),
+        }
+    }
+
+    /// The name `--type` takes and messages use.
+    fn name(self) -> &'static str {
+        match self {
+            EventType::Events => "events",
+            EventType::Births => "births",
+            EventType::Deaths => "deaths",
+            EventType::Holidays => "holidays",
+            EventType::Selected => "selected",
+            EventType::All => "all",
+            EventType::Anniversaries => "anniversaries",
+            EventType::Featured => "featured",
+        }
+    }
+}
+
+impl std::fmt::Display for EventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct OnThisDayResponse {
+    #[serde(default)]
+    pub events: Vec<Event>,
+    #[serde(default)]
+    pub births: Vec<Event>,
+    #[serde(default)]
+    pub deaths: Vec<Event>,
+    #[serde(default)]
+    pub holidays: Vec<Holiday>,
+    #[serde(default)]
+    pub selected: Vec<Event>,
+}
+
+impl OnThisDayResponse {
+    /// The year-bearing entries for `event_type` (`None` for holidays and
+    /// the combined types).
+    pub fn events_of(&self, event_type: EventType) -> Option<&[Event]> {
+        match event_type {
+            EventType::Events => Some(&self.events),
+            EventType::Births => Some(&self.births),
+            EventType::Deaths => Some(&self.deaths),
+            EventType::Selected => Some(&self.selected),
+            EventType::Holidays
+            | EventType::All
+            | EventType::Anniversaries
+            | EventType::Featured => None,
+        }
+    }
+
+    /// Mutable access to what [`Self::events_of`] returns.
+    pub fn events_of_mut(
+        &mut self,
+        event_type: EventType,
+    ) -> Option<&mut Vec<Event>> {
+        match event_type {
+            EventType::Events => Some(&mut self.events),
+            EventType::Births => Some(&mut self.births),
+            EventType::Deaths => Some(&mut self.deaths),
+            EventType::Selected => Some(&mut self.selected),
+            EventType::Holidays
+            | EventType::All
+            | EventType::Anniversaries
+            | EventType::Featured => None,
+        }
+    }
+
+    /// Keep only year-bearing entries within the inclusive bounds; holidays
+    /// have no year and are left alone.
+    pub fn retain_years(&mut self, from: Option<i32>, to: Option<i32>) {
+        let keep = |ev: &Event| {
+            from.is_none_or(|from| ev.year >= from)
+                && to.is_none_or(|to| ev.year <= to)
+        };
+        self.events.retain(keep);
+        self.births.retain(keep);
+        self.deaths.retain(keep);
+        self.selected.retain(keep);
+    }
+
+    /// Keep only entries (holidays included) whose text contains `needle`,
+    /// ignoring case unless `case_sensitive`.
+    pub fn retain_matching(&mut self, needle: &str, case_sensitive: bool) {
+        let matches = text_matcher(needle, case_sensitive);
+        self.events.retain(|ev| matches(&ev.text));
+        self.births.retain(|ev| matches(&ev.text));
+        self.deaths.retain(|ev| matches(&ev.text));
+        self.selected.retain(|ev| matches(&ev.text));
+        self.holidays.retain(|holiday| matches(&holiday.text));
+    }
+
+    /// Keep only holidays whose text contains `needle`, ignoring case
+    /// unless `case_sensitive`; the dated sections are left alone.
+    pub fn retain_holidays_matching(
+        &mut self,
+        needle: &str,
+        case_sensitive: bool,
+    ) {
+        let matches = text_matcher(needle, case_sensitive);
+        self.holidays.retain(|holiday| matches(&holiday.text));
+    }
+
+    /// Keep only entries (holidays included) whose text runs to at least
+    /// `min_chars` characters.
+    pub fn retain_min_length(&mut self, min_chars: usize) {
+        let long_enough = |text: &str| text.chars().count() >= min_chars;
+        self.events.retain(|ev| long_enough(&ev.text));
+        self.births.retain(|ev| long_enough(&ev.text));
+        self.deaths.retain(|ev| long_enough(&ev.text));
+        self.selected.retain(|ev| long_enough(&ev.text));
+        self.holidays.retain(|holiday| long_enough(&holiday.text));
+    }
+
+    /// Drop entries whose text repeats an earlier one in the same section,
+    /// ignoring case and surrounding whitespace; returns how many went.
+    pub fn dedupe(&mut self) -> usize {
+        fn dedupe_by<T>(items: &mut Vec<T>, text: fn(&T) -> &str) -> usize {
+            let before = items.len();
+            let mut seen = HashSet::new();
+            items.retain(|item| seen.insert(text(item).trim().to_lowercase()));
+            before - items.len()
+        }
+        dedupe_by(&mut self.events, |ev| &ev.text)
+            + dedupe_by(&mut self.births, |ev| &ev.text)
+            + dedupe_by(&mut self.deaths, |ev| &ev.text)
+            + dedupe_by(&mut self.holidays, |h| &h.text)
+            + dedupe_by(&mut self.selected, |ev| &ev.text)
+    }
+
+    /// Put the dated sections in the feed's nominal order, newest first,
+    /// which the tables and lists read backwards as oldest first. Entries
+    /// from the same year keep their order.
+    pub fn sort_by_year(&mut self) {
+        let newest_first = |ev: &Event| std::cmp::Reverse(ev.year);
+        self.events.sort_by_key(newest_first);
+        self.births.sort_by_key(newest_first);
+        self.deaths.sort_by_key(newest_first);
+        self.selected.sort_by_key(newest_first);
+    }
+
+    /// Flip the order of every section.
+    pub fn reverse(&mut self) {
+        self.events.reverse();
+        self.births.reverse();
+        self.deaths.reverse();
+        self.holidays.reverse();
+        self.selected.reverse();
+    }
+
+    /// Keep at most `n` entries per section: the first `n` listed, which for
+    /// the dated sections (listed back to front) are the feed's last `n`.
+    pub fn truncate_shown(&mut self, n: usize) {
+        fn keep_last<T>(items: &mut Vec<T>, n: usize) {
+            items.drain(..items.len().saturating_sub(n));
+        }
+        keep_last(&mut self.events, n);
+        keep_last(&mut self.births, n);
+        keep_last(&mut self.deaths, n);
+        keep_last(&mut self.selected, n);
+        self.holidays.truncate(n);
+    }
+
+    /// Whether the feed has no entries of any kind, as some wikis answer
+    /// `{}` for days (or whole feeds) they don't cover.
+    pub fn is_blank(&self) -> bool {
+        self.events.is_empty()
+            && self.births.is_empty()
+            && self.deaths.is_empty()
+            && self.holidays.is_empty()
+            && self.selected.is_empty()
+    }
+
+    /// Whether there's nothing at all to show for `event_type`.
+    pub fn is_empty_for(&self, event_type: EventType) -> bool {
+        event_type.parts().iter().all(|part| match self.events_of(*part) {
+            Some(events) => events.is_empty(),
+            None => self.holidays.is_empty(),
+        })
+    }
+}
+
+/// Substring test behind `--search` and `--holiday-filter`, ignoring case
+/// unless `case_sensitive`.
+fn text_matcher(needle: &str, case_sensitive: bool) -> impl Fn(&str) -> bool {
+    let needle = if case_sensitive {
+        needle.to_string()
+    } else {
+        needle.to_lowercase()
+    };
+    move |text: &str| {
+        if case_sensitive {
+            text.contains(&needle)
+        } else {
+            text.to_lowercase().contains(&needle)
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone)]
+pub struct Event {
+    pub year: i32,
+    pub text: String,
+    /// Desktop URL of the first article linked from the entry
+    #[serde(
+        default,
+        rename(deserialize = "pages"),
+        deserialize_with = "first_page_url",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Page {
+    content_urls: Option<ContentUrls>,
+}
+
+#[derive(Deserialize)]
+struct ContentUrls {
+    desktop: Option<PageUrl>,
+}
+
+#[derive(Deserialize)]
+struct PageUrl {
+    page: String,
+}
+
+fn first_page_url<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<String>, D::Error> {
+    let pages = Vec::<Page>::deserialize(deserializer)?;
+    Ok(pages
+        .into_iter()
+        .find_map(|page| page.content_urls?.desktop.map(|url| url.page)))
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone)]
+pub struct Holiday {
+    pub text: String,
+}
+
+/// The part of the featured-content feed `--type featured` uses.
+#[derive(Deserialize, Debug)]
+struct FeaturedResponse {
+    image: Option<FeaturedImage>,
+}
+
+/// Wikimedia Commons' picture of the day.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FeaturedImage {
+    /// The file name, e.g. `File:Moon.jpg`
+    pub title: String,
+    #[serde(
+        default,
+        deserialize_with = "description_text",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description: Option<String>,
+    /// The image's description page on Commons
+    pub file_page: String,
+}
+
+fn description_text<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    struct Description {
+        text: String,
+    }
+    let description = Option::<Description>::deserialize(deserializer)?;
+    Ok(description.map(|description| description.text))
+}
+
+/// A parsed feed together with where and when it was fetched.
+#[derive(Debug, Clone)]
+pub struct Fetched {
+    pub lang: String,
+    pub url: String,
+    /// Unix seconds; older than now when served from the cache
+    pub fetched_at: i64,
+    pub response: OnThisDayResponse,
+    pub metrics: FetchMetrics,
+    /// Why the refresh failed, when [`CachePolicy::stale_ok`] served the
+    /// cached copy from `fetched_at` instead
+    pub refresh_error: Option<String>,
+}
+
+/// What a fetch cost, for `--verbose`'s request log and end-of-run summary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FetchMetrics {
+    /// Body bytes that came over the network (0 when a cache answered)
+    pub bytes: usize,
+    /// Time spent turning the body into an [`OnThisDayResponse`]
+    pub parse_time: Duration,
+    /// Whether a cache supplied the body (including a revalidated copy)
+    pub cache_hit: bool,
+    /// The response's status, if a request went out (a fresh cached copy
+    /// answers without one)
+    pub status: Option<StatusCode>,
+    /// From sending the request to having its whole body
+    pub round_trip: Duration,
+}
+
+/// A day's picture of the day (`None` if it has none) and where and when
+/// it was fetched, like [`Fetched`] for a feed.
+#[derive(Debug, Clone)]
+pub struct FetchedImage {
+    pub lang: String,
+    pub url: String,
+    /// Unix seconds; older than now when served from the cache
+    pub fetched_at: i64,
+    pub image: Option<FeaturedImage>,
+    pub metrics: FetchMetrics,
+    /// Why the refresh failed, when [`CachePolicy::stale_ok`] served the
+    /// cached copy from `fetched_at` instead
+    pub refresh_error: Option<String>,
+}
+
+/* --------------------------------------------------------------------------
+ *                                client
+ * ---------------------------------------------------------------------- */
+
+/// Upper bound on simultaneous requests when fetching several days.
+const FETCH_CONCURRENCY: usize = 4;
+
+/// `(lang, API segment, month, day)` of a feed request.
+type FetchKey = (String, &'static str, u32, u32);
+
+/// User-Agent sent unless [`FetchOptions::user_agent`] says otherwise;
+/// Wikimedia asks for a way to contact the tool's maintainers.
+const DEFAULT_USER_AGENT: &str = concat!(
+    env!("CARGO_PKG_NAME"),
+    '/',
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/12Mosch/time_cli)",
+);
+
+/// How a [`WikiClient`] reaches Wikipedia.
+#[derive(Debug, Clone, Default)]
+pub struct FetchOptions {
+    /// User-Agent for every request (default: name/version plus the
+    /// project URL)
+    pub user_agent: Option<String>,
+    /// Replaces `https://{lang}.wikipedia.org`, e.g. with a local mock; a
+    /// `{lang}` placeholder in it is filled in like the real host
+    pub base_url: Option<String>,
+    pub policy: CachePolicy,
+    /// Called with each URL just before it's requested, whether or not
+    /// the request then succeeds (cache hits send nothing)
+    pub on_request: Option<fn(&str)>,
+}
+
+/// Fetches On This Day feeds and featured pictures as its
+/// [`FetchOptions`] say, remembering each feed it fetched for the rest of
+/// its life.
+#[derive(Debug)]
+pub struct WikiClient {
+    http: Client,
+    options: FetchOptions,
+    /// Feeds already fetched, with when they were stored
+    memory: Mutex<HashMap<FetchKey, (Instant, Fetched)>>,
+}
+
+/* --------------------------------------------------------------------------
+ *                              disk cache
+ * ---------------------------------------------------------------------- */
+
+/// How long a cached response is served without asking Wikipedia again,
+/// unless `--cache-ttl` says otherwise.
+pub const CACHE_TTL_SECS: i64 = 86_400;
+
+/// A raw feed response persisted between runs, together with the
+/// validators needed for a conditional refresh.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CacheEntry {
+    fetched_at: i64, // unix seconds
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+impl CacheEntry {
+    fn is_fresh(&self, now: i64, ttl_secs: i64) -> bool {
+        now - self.fetched_at < ttl_secs
+    }
+}
+
+/// How a fetch may use the caches and the network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CachePolicy {
+    /// Answer from the disk cache only, however old; never go online
+    pub offline: bool,
+    /// Skip reading either cache but still write the fresh result to both
+    pub refresh: bool,
+    /// Fall back to the disk-cache entry, however old, if the fetch fails
+    pub stale_ok: bool,
+    /// Serve cached responses younger than this; 0 turns caching off
+    pub ttl_secs: i64,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            offline: false,
+            refresh: false,
+            stale_ok: false,
+            ttl_secs: CACHE_TTL_SECS,
+        }
+    }
+}
+
+impl CachePolicy {
+    fn caches(self) -> bool {
+        self.ttl_secs > 0
+    }
+
+    /// Whether a cached copy may be served instead of going online.
+    fn reads_cache(self) -> bool {
+        self.offline || (self.caches() && !self.refresh)
+    }
+}
+
+/// Directory for cached responses; `TIME_CLI_CACHE_DIR` overrides the
+/// platform cache directory.
+fn cache_dir() -> Option<PathBuf> {
+    std::env::var_os("TIME_CLI_CACHE_DIR")
+        .map(PathBuf::from)
+        .or_else(|| dirs::cache_dir().map(|dir| dir.join("time_cli")))
+}
+
+fn load_cache_entry(path: &Path) -> Option<CacheEntry> {
+    let raw = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+fn store_cache_entry(path: &Path, entry: &CacheEntry) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/* --------------------------------------------------------------------------
+ *                              Wikipedia
+ * ---------------------------------------------------------------------- */
+
+/// What [`WikiClient::fetch_with_disk_cache`] got for a URL.
+struct Loaded<T> {
+    response: T,
+    /// Unix seconds
+    fetched_at: i64,
+    metrics: FetchMetrics,
+    refresh_error: Option<String>,
+}
+
+impl WikiClient {
+    pub fn new(options: FetchOptions) -> Result<Self> {
+        let user_agent =
+            options.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+        let http = Client::builder()
+            .user_agent(user_agent)
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        Ok(Self {
+            http,
+            options,
+            memory: Mutex::default(),
+        })
+    }
+
+    /// Fetch one day's `event_type` feed from the `lang` Wikipedia: from
+    /// this client's memory if it already has a young enough copy, else
+    /// through the disk cache. Only successes are remembered.
+    ///
+    /// ```
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() -> anyhow::Result<()> {
+    /// use time_cli::{CachePolicy, EventType, FetchOptions, WikiClient};
+    ///
+    /// let mut server = mockito::Server::new_async().await;
+    /// server
+    ///     .mock("GET", "/api/rest_v1/feed/onthisday/holidays/7/20")
+    ///     .with_body(r#"{"holidays": [{"text": "Moon Day"}]}"#)
+    ///     .create_async()
+    ///     .await;
+    ///
+    /// let wiki = WikiClient::new(FetchOptions {
+    ///     base_url: Some(server.url()),
+    ///     // Straight from the network, leaving the disk cache alone
+    ///     policy: CachePolicy {
+    ///         ttl_secs: 0,
+    ///         ..CachePolicy::default()
+    ///     },
+    ///     ..FetchOptions::default()
+    /// })?;
+    /// let day = wiki.fetch_day("en", EventType::Holidays, 7, 20).await?;
+    /// assert_eq!(day.response.holidays[0].text, "Moon Day");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_day(
+        &self,
+        lang: &str,
+        event_type: EventType,
+        month: u32,
+        day: u32,
+    ) -> Result<Fetched> {
+        if event_type == EventType::Featured {
+            bail!("the featured picture isn't part of the On This Day feed");
+        }
+        let policy = self.options.policy;
+        let key = (lang.to_string(), event_type.api_segment(), month, day);
+        if policy.caches() && policy.reads_cache() {
+            let memory = self.memory.lock().unwrap();
+            if let Some((stored, fetched)) = memory.get(&key)
+                && stored.elapsed().as_secs() < policy.ttl_secs as u64
+            {
+                let metrics = FetchMetrics {
+                    cache_hit: true,
+                    ..FetchMetrics::default()
+                };
+                return Ok(Fetched {
+                    metrics,
+                    refresh_error: None,
+                    ..fetched.clone()
+                });
+            }
+        }
+
+        let FeedLocation { url, cache_path } =
+            self.feed_location(lang, event_type, month, day)?;
+        let loaded =
+            self.fetch_with_disk_cache(&url, cache_path.as_deref()).await?;
+        let fetched = Fetched {
+            lang: lang.to_string(),
+            url,
+            fetched_at: loaded.fetched_at,
+            response: loaded.response,
+            metrics: loaded.metrics,
+            refresh_error: loaded.refresh_error,
+        };
+        if policy.caches() {
+            self.memory
+                .lock()
+                .unwrap()
+                .insert(key, (Instant::now(), fetched.clone()));
+        }
+        Ok(fetched)
+    }
+
+    /// The URL and cache file [`WikiClient::fetch_day`] would use for one
+    /// day's `event_type` feed.
+    pub fn feed_location(
+        &self,
+        lang: &str,
+        event_type: EventType,
+        month: u32,
+        day: u32,
+    ) -> Result<FeedLocation> {
+        let segment = event_type.api_segment();
+        let base_url = api_base_url(self.options.base_url.as_deref(), lang)?;
+        let url = feed_url(&base_url, segment, month, day)?;
+        let cache_path = cache_dir().map(|dir| {
+            dir.join(format!("{lang}-{segment}-{month:02}-{day:02}.json"))
+        });
+        Ok(FeedLocation {
+            url: url.to_string(),
+            cache_path,
+        })
+    }
+
+    /// The URL and cache file [`WikiClient::fetch_featured_image`] would
+    /// use for `date`'s picture of the day.
+    pub fn featured_location(
+        &self,
+        lang: &str,
+        date: NaiveDate,
+    ) -> Result<FeedLocation> {
+        let base_url = api_base_url(self.options.base_url.as_deref(), lang)?;
+        let url = featured_url(&base_url, date)?;
+        let cache_path = cache_dir()
+            .map(|dir| dir.join(format!("{lang}-featured-{date}.json")));
+        Ok(FeedLocation {
+            url: url.to_string(),
+            cache_path,
+        })
+    }
+
+    /// Fetch `date`'s picture of the day from the `lang` wiki, through the
+    /// disk cache like the On This Day feeds.
+    pub async fn fetch_featured_image(
+        &self,
+        lang: &str,
+        date: NaiveDate,
+    ) -> Result<FetchedImage> {
+        let FeedLocation { url, cache_path } =
+            self.featured_location(lang, date)?;
+        let loaded: Loaded<FeaturedResponse> =
+            self.fetch_with_disk_cache(&url, cache_path.as_deref()).await?;
+        Ok(FetchedImage {
+            lang: lang.to_string(),
+            url,
+            fetched_at: loaded.fetched_at,
+            image: loaded.response.image,
+            metrics: loaded.metrics,
+            refresh_error: loaded.refresh_error,
+        })
+    }
+
+    /// Fetch `days` with at most [`FETCH_CONCURRENCY`] requests in flight.
+    /// Every day keeps its own outcome (in the order given), so one
+    /// failure doesn't sink the batch; `on_done` sees the running count of
+    /// finished requests as they complete.
+    pub async fn fetch_days(
+        &self,
+        langs: &[String],
+        event_type: EventType,
+        days: &[NaiveDate],
+        on_done: impl FnMut(usize),
+    ) -> Vec<Result<Fetched>> {
+        fetch_each(days, on_done, |date| {
+            self.fetch_with_fallback(langs, event_type, date)
+        })
+        .await
+    }
+
+    /// [`WikiClient::fetch_days`] for the picture of the day: each day
+    /// from the first of `langs` that doesn't answer 404.
+    pub async fn fetch_featured_days(
+        &self,
+        langs: &[String],
+        days: &[NaiveDate],
+        on_done: impl FnMut(usize),
+    ) -> Vec<Result<FetchedImage>> {
+        fetch_each(days, on_done, |date| async move {
+            let mut first = None;
+            for lang in langs {
+                let result = self.fetch_featured_image(lang, date).await;
+                match &result {
+                    Err(err) if is_not_found(err) => {
+                        first.get_or_insert(result);
+                    }
+                    _ => return result,
+                }
+            }
+            first.unwrap_or_else(|| Err(anyhow!("no language to fetch from")))
+        })
+        .await
+    }
+
+    /// Fetch one day from the first of `langs` that has entries of this
+    /// type, moving on after an empty feed or a 404; other errors end the
+    /// search. When no language has anything, the first one's answer
+    /// stands.
+    async fn fetch_with_fallback(
+        &self,
+        langs: &[String],
+        event_type: EventType,
+        date: NaiveDate,
+    ) -> Result<Fetched> {
+        let mut first = None;
+        for lang in langs {
+            let result = self
+                .fetch_day(lang, event_type, date.month(), date.day())
+                .await;
+            let usable = match &result {
+                Ok(fetched) => !fetched.response.is_empty_for(event_type),
+                Err(err) => !is_not_found(err),
+            };
+            if usable {
+                return result;
+            }
+            first.get_or_insert(result);
+        }
+        first.unwrap_or_else(|| Err(anyhow!("no language to fetch from")))
+    }
+
+    /// GET `url`, answering from a fresh disk-cache entry when possible and
+    /// revalidating a stale one with `If-None-Match` / `If-Modified-Since`.
+    ///
+    /// With `stale_ok` in the policy a failed request falls back to the
+    /// cached entry, whatever its age, and says why in `refresh_error`.
+    async fn fetch_with_disk_cache<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        cache_path: Option<&Path>,
+    ) -> Result<Loaded<T>> {
+        let policy = self.options.policy;
+        let now = chrono::Utc::now().timestamp();
+        let cache_path =
+            cache_path.filter(|_| policy.caches() || policy.offline);
+        let stored = cache_path
+            .filter(|_| policy.reads_cache() || policy.stale_ok)
+            .and_then(load_cache_entry);
+        let cached = stored.clone().filter(|_| policy.reads_cache());
+
+        if let Some(entry) = &cached
+            && (entry.is_fresh(now, policy.ttl_secs) || policy.offline)
+        {
+            return Loaded::from_cache(entry, None);
+        }
+        if policy.offline {
+            bail!("No cached copy of {url}; run once without --offline first");
+        }
+
+        let sent = Instant::now();
+        let (entry, hit, status) = match self.revalidate(url, cached, now).await
+        {
+            Ok(fetched) => fetched,
+            Err(err) => match stored.filter(|_| policy.stale_ok) {
+                Some(entry) => {
+                    return Loaded::from_cache(&entry, Some(err.to_string()));
+                }
+                None => return Err(err),
+            },
+        };
+        let round_trip = sent.elapsed();
+
+        // Parse before storing so a broken body never ends up in the cache
+        let received = if hit { 0 } else { entry.body.len() };
+        let (response, metrics) = parse_feed(&entry.body, received, hit)?;
+        if let Some(path) = cache_path {
+            // Best effort: a read-only cache dir shouldn't fail the command
+            let _ = store_cache_entry(path, &entry);
+        }
+        Ok(Loaded {
+            response,
+            fetched_at: entry.fetched_at,
+            metrics: FetchMetrics {
+                status: Some(status),
+                round_trip,
+                ..metrics
+            },
+            refresh_error: None,
+        })
+    }
+
+    /// Tell [`FetchOptions::on_request`] that `url` is about to be sent.
+    fn announce(&self, url: &str) {
+        if let Some(on_request) = self.options.on_request {
+            on_request(url);
+        }
+    }
+
+    /// Request `url`, conditionally when there's a `cached` entry to
+    /// revalidate. Returns the entry to keep, whether its body is the
+    /// cached one (upstream answered 304) and the response's status.
+    async fn revalidate(
+        &self,
+        url: &str,
+        cached: Option<CacheEntry>,
+        now: i64,
+    ) -> Result<(CacheEntry, bool, StatusCode)> {
+        self.announce(url);
+        let mut request = self.http.get(url);
+        if let Some(entry) = &cached {
+            if let Some(etag) = &entry.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|err| explain_network_error(err, url))?;
+        let status = response.status();
+
+        match cached {
+            // Unchanged upstream: keep the body, restart the TTL
+            Some(entry) if status == StatusCode::NOT_MODIFIED => {
+                let entry = CacheEntry {
+                    fetched_at: now,
+                    ..entry
+                };
+                Ok((entry, true, status))
+            }
+            _ => {
+                let response = response.error_for_status()?;
+                let header = |name| {
+                    response
+                        .headers()
+                        .get(name)
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_owned)
+                };
+                let (etag, last_modified) =
+                    (header(ETAG), header(LAST_MODIFIED));
+                let body = response
+                    .text()
+                    .await
+                    .map_err(|err| explain_network_error(err, url))?;
+                let entry = CacheEntry {
+                    fetched_at: now,
+                    etag,
+                    last_modified,
+                    body,
+                };
+                Ok((entry, false, status))
+            }
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Loaded<T> {
+    /// A cache entry served as is, instead of a refresh that failed with
+    /// `refresh_error` if there was one.
+    fn from_cache(
+        entry: &CacheEntry,
+        refresh_error: Option<String>,
+    ) -> Result<Self> {
+        let (response, metrics) = parse_feed(&entry.body, 0, true)?;
+        Ok(Self {
+            response,
+            fetched_at: entry.fetched_at,
+            metrics,
+            refresh_error,
+        })
+    }
+}
+
+/// Where one day's feed is fetched from and kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedLocation {
+    pub url: String,
+    /// The disk-cache file (`None` without a cache directory)
+    pub cache_path: Option<PathBuf>,
+}
+
+/// The Wikipedia host for `lang` (or `base_url` standing in for it), where
+/// `lang` must look like a language code since it becomes part of the
+/// host name (and of cache file names).
+fn api_base_url(base_url: Option<&str>, lang: &str) -> Result<String> {
+    if lang.is_empty()
+        || !lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        bail!("'{lang}' is not a usable Wikipedia language code");
+    }
+    Ok(match base_url {
+        Some(url) => url.replace("{lang}", lang),
+        None => format!("https://{lang}.wikipedia.org"),
+    })
+}
+
+/// The On This Day endpoint for one day under `base_url`; the dynamic
+/// parts are added as percent-encoded path segments, so they can't
+/// escape their place in the URL.
+fn feed_url(
+    base_url: &str,
+    event_type: &str,
+    month: u32,
+    day: u32,
+) -> Result<Url> {
+    let mut url = Url::parse(base_url)?;
+    url.path_segments_mut()
+        .map_err(|()| anyhow!("{base_url} can't serve as an API base URL"))?
+        .pop_if_empty()
+        .extend(["api", "rest_v1", "feed", "onthisday", event_type])
+        .extend([month.to_string(), day.to_string()]);
+    Ok(url)
+}
+
+/// The featured-content endpoint (picture of the day and friends) for
+/// `date` under `base_url`.
+fn featured_url(base_url: &str, date: NaiveDate) -> Result<Url> {
+    let mut url = Url::parse(base_url)?;
+    url.path_segments_mut()
+        .map_err(|()| anyhow!("{base_url} can't serve as an API base URL"))?
+        .pop_if_empty()
+        .extend(["api", "rest_v1", "feed", "featured"])
+        .extend(["%Y", "%m", "%d"].map(|part| date.format(part).to_string()));
+    Ok(url)
+}
+
+/// Parse a feed body, timing it for [`FetchMetrics`].
+fn parse_feed<T: DeserializeOwned>(
+    body: &str,
+    bytes: usize,
+    cache_hit: bool,
+) -> Result<(T, FetchMetrics)> {
+    let start = Instant::now();
+    let response = serde_json::from_str(body)?;
+    let metrics = FetchMetrics {
+        bytes,
+        parse_time: start.elapsed(),
+        cache_hit,
+        ..FetchMetrics::default()
+    };
+    Ok((response, metrics))
+}
+
+/// Run `fetch` for each of `days` with at most [`FETCH_CONCURRENCY`] in
+/// flight, keeping every outcome in the order given; `on_done` sees the
+/// running count of finished days.
+async fn fetch_each<T, F, Fut>(
+    days: &[NaiveDate],
+    mut on_done: impl FnMut(usize),
+    fetch: F,
+) -> Vec<Result<T>>
+where
+    F: Fn(NaiveDate) -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut done = 0;
+    let mut results: Vec<(usize, Result<T>)> =
+        stream::iter(days.iter().enumerate())
+            .map(|(i, date)| {
+                let response = fetch(*date);
+                async move { (i, response.await) }
+            })
+            .buffer_unordered(FETCH_CONCURRENCY)
+            .inspect(|_| {
+                done += 1;
+                on_done(done);
+            })
+            .collect()
+            .await;
+
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Put a short, friendly headline on connection failures and timeouts;
+/// the original error stays in the chain for `--verbose`.
+fn explain_network_error(err: reqwest::Error, url: &str) -> anyhow::Error {
+    if !(err.is_connect() || err.is_timeout()) {
+        return err.into();
+    }
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_owned))
+        .unwrap_or_else(|| url.to_owned());
+    anyhow::Error::new(err).context(format!(
+        "Could not reach {host} — check your connection (or use --offline)",
+    ))
+}
+
+fn is_not_found(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .and_then(reqwest::Error::status)
+        == Some(StatusCode::NOT_FOUND)
+}
+
+/* --------------------------------------------------------------------------
+ *                            time statistics
+ * ---------------------------------------------------------------------- */
+
+#[derive(Debug, Copy, Clone, Serialize, JsonSchema)]
+#[must_use]
+pub struct TimeStats {
+    pub day_of_year: u32,
+    pub total_days_in_year: u32,
+    pub day_progress: f64,  // 0–100
+    pub year_progress: f64, // 0–100
+    /// How far through the ISO week (Monday to Sunday) the moment is
+    pub week_progress: f64, // 0–100
+    pub week_of_year: u32,
+    pub is_leap: bool,
+    pub unix_timestamp: i64,
+    /// Length of today in seconds, 86 400 but for DST changes
+    #[serde(skip)]
+    pub seconds_in_day: i64,
+}
+
+impl TimeStats {
+    /// Seconds left until the next day starts.
+    pub fn seconds_remaining_in_day(&self) -> i64 {
+        let left = 1.0 - self.day_progress / 100.0;
+        (self.seconds_in_day as f64 * left).round() as i64
+    }
+
+    /// Whole days left in the year after today (0 on Dec 31).
+    pub fn days_remaining_in_year(&self) -> u32 {
+        self.total_days_in_year - self.day_of_year
+    }
+
+    /// Whole weeks in [`Self::days_remaining_in_year`].
+    pub fn weeks_remaining_in_year(&self) -> u32 {
+        self.days_remaining_in_year() / 7
+    }
+}
+
+/// The instant `date` begins in `zone` (`None` = local). That's usually
+/// midnight, but where a DST gap swallows midnight the day starts at the
+/// end of the gap.
+fn start_of_day(zone: Option<Tz>, date: NaiveDate) -> DateTime<Utc> {
+    fn first_instant<Z: TimeZone>(
+        zone: &Z,
+        date: NaiveDate,
+    ) -> DateTime<Utc> {
+        let midnight = date.and_time(Default::default());
+        (0..=24)
+            .find_map(|hour| {
+                let wall = midnight + TimeDelta::hours(hour);
+                zone.from_local_datetime(&wall).earliest()
+            })
+            .map(|start| start.with_timezone(&Utc))
+            .unwrap_or_else(|| midnight.and_utc())
+    }
+    match zone {
+        Some(tz) => first_instant(&tz, date),
+        None => first_instant(&Local, date),
+    }
+}
+
+/// `zone` is the one `now` was taken in (`None` = local); it decides how
+/// long today is, which is 23 or 25 hours on DST transition days.
+pub fn compute_time_statistics(
+    now: DateTime<FixedOffset>,
+    zone: Option<Tz>,
+) -> TimeStats {
+    let year = now.year();
+    let is_leap = NaiveDate::from_ymd_opt(year, 2, 29).is_some();
+
+    let today = now.date_naive();
+    let start = start_of_day(zone, today);
+    let end = today
+        .succ_opt()
+        .map_or(start + TimeDelta::days(1), |tomorrow| {
+            start_of_day(zone, tomorrow)
+        });
+    let seconds_into_day = (now.with_timezone(&Utc) - start).num_seconds();
+    let seconds_in_day = (end - start).num_seconds();
+    let day_progress =
+        (seconds_into_day as f64 / seconds_in_day as f64) * 100.0;
+
+    let day_of_year = now.ordinal();
+    let total_days_in_year = if is_leap { 366 } else { 365 };
+    let year_progress =
+        (day_of_year as f64 / total_days_in_year as f64) * 100.0;
+
+    let days_into_week = f64::from(now.weekday().num_days_from_monday());
+    let week_progress = (days_into_week + day_progress / 100.0) / 7.0 * 100.0;
+
+    TimeStats {
+        day_of_year,
+        total_days_in_year,
+        day_progress,
+        year_progress,
+        week_progress,
+        week_of_year: now.iso_week().week(),
+        is_leap,
+        unix_timestamp: now.timestamp(),
+        seconds_in_day,
+    }
+}
+
+/// When the sun is up on a given day at a given place.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Daylight {
+    Span {
+        rise: DateTime<Utc>,
+        set: DateTime<Utc>,
+    },
+    /// The sun never sets
+    MidnightSun,
+    /// The sun never rises
+    PolarNight,
+}
+
+/// Sunrise and sunset on `date` at `lat`/`lon` (degrees, north/east
+/// positive), by the sunrise equation with the usual -0.833° correction
+/// for refraction and the sun's disc. Good to a minute or two outside
+/// the polar circles.
+pub fn daylight(date: NaiveDate, lat: f64, lon: f64) -> Daylight {
+    const J2000: f64 = 2_451_545.0;
+    const UNIX_EPOCH_JD: f64 = 2_440_587.5;
+
+    let midnight = date.and_time(Default::default()).and_utc().timestamp();
+    let days = (midnight as f64 / 86_400.0 + UNIX_EPOCH_JD - J2000 + 0.0008)
+        .ceil();
+    let mean_noon = days - lon / 360.0;
+
+    let anomaly = (357.5291 + 0.985_600_28 * mean_noon)
+        .rem_euclid(360.0)
+        .to_radians();
+    let center = 1.9148 * anomaly.sin()
+        + 0.0200 * (2.0 * anomaly).sin()
+        + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic_lon = (anomaly.to_degrees() + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = J2000 + mean_noon + 0.0053 * anomaly.sin()
+        - 0.0069 * (2.0 * ecliptic_lon).sin();
+    let declination =
+        (ecliptic_lon.sin() * 23.4397_f64.to_radians().sin()).asin();
+
+    // Keep clear of the poles, where the hour angle is 0/0
+    let lat = lat.clamp(-89.99, 89.99).to_radians();
+    let cos_hour_angle = ((-0.833_f64).to_radians().sin()
+        - lat.sin() * declination.sin())
+        / (lat.cos() * declination.cos());
+    if cos_hour_angle > 1.0 {
+        return Daylight::PolarNight;
+    }
+    if cos_hour_angle < -1.0 {
+        return Daylight::MidnightSun;
+    }
+
+    let half_day = cos_hour_angle.acos().to_degrees() / 360.0;
+    let to_utc = |julian: f64| {
+        let secs = ((julian - UNIX_EPOCH_JD) * 86_400.0).round() as i64;
+        DateTime::from_timestamp(secs, 0).unwrap()
+    };
+    Daylight::Span {
+        rise: to_utc(transit - half_day),
+        set: to_utc(transit + half_day),
+    }
+}
+
+/* --------------------------------------------------------------------------
+ *                                 tests
+ * ---------------------------------------------------------------------- */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leap_year_statistics() {
+        let dt = Local
+            .with_ymd_and_hms(2024, 3, 1, 0, 0, 0)
+            .unwrap()
+            .fixed_offset();
+        let stats = compute_time_statistics(dt, None);
+        assert!(stats.is_leap);
+        assert_eq!(stats.total_days_in_year, 366);
+        // 1 March in a leap year is day 61
+        assert_eq!(stats.day_of_year, 61);
+    }
+
+    #[test]
+    fn non_leap_year() {
+        let dt = Local
+            .with_ymd_and_hms(2025, 3, 1, 0, 0, 0)
+            .unwrap()
+            .fixed_offset();
+        let stats = compute_time_statistics(dt, None);
+        assert!(!stats.is_leap);
+        assert_eq!(stats.total_days_in_year, 365);
+    }
+
+    #[test]
+    fn week_progress_counts_from_monday() {
+        // Thursday 12:00 is 3.5 days into the week
+        let dt = Utc
+            .with_ymd_and_hms(2024, 3, 7, 12, 0, 0)
+            .unwrap()
+            .fixed_offset();
+        let stats = compute_time_statistics(dt, Some(Tz::UTC));
+        assert!((stats.week_progress - 50.0).abs() < 1e-9);
+
+        let monday = Utc.with_ymd_and_hms(2024, 3, 4, 0, 0, 0).unwrap();
+        let stats =
+            compute_time_statistics(monday.fixed_offset(), Some(Tz::UTC));
+        assert_eq!(stats.week_progress, 0.0);
+    }
+
+    #[test]
+    fn day_progress_on_spring_forward_day() {
+        // New York skips 02:00–03:00 on 10 March 2024: a 23-hour day
+        let tz = Tz::America__New_York;
+        let dt = tz
+            .with_ymd_and_hms(2024, 3, 10, 1, 30, 0)
+            .unwrap()
+            .fixed_offset();
+        let stats = compute_time_statistics(dt, Some(tz));
+        let naive = 1.5 / 24.0 * 100.0;
+        assert!(stats.day_progress > naive);
+        assert!((stats.day_progress - 1.5 / 23.0 * 100.0).abs() < 1e-9);
+
+        let last_second = tz
+            .with_ymd_and_hms(2024, 3, 10, 23, 59, 59)
+            .unwrap()
+            .fixed_offset();
+        let stats = compute_time_statistics(last_second, Some(tz));
+        assert!(stats.day_progress > 99.99 && stats.day_progress < 100.0);
+    }
+
+    #[test]
+    fn remaining_time() {
+        let at = |y, m, d, h, min| {
+            let dt = Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap();
+            compute_time_statistics(dt.fixed_offset(), Some(Tz::UTC))
+        };
+
+        let stats = at(2024, 3, 1, 18, 48);
+        assert_eq!(stats.seconds_remaining_in_day(), 5 * 3600 + 12 * 60);
+        assert_eq!(stats.days_remaining_in_year(), 366 - 61);
+        assert_eq!(stats.weeks_remaining_in_year(), 43);
+
+        // Dec 31 is day 366 in a leap year, 365 otherwise: none left
+        assert_eq!(at(2024, 12, 31, 0, 0).days_remaining_in_year(), 0);
+        assert_eq!(at(2023, 12, 31, 0, 0).days_remaining_in_year(), 0);
+        assert_eq!(at(2024, 2, 28, 0, 0).days_remaining_in_year(), 307);
+        assert_eq!(at(2023, 2, 28, 0, 0).days_remaining_in_year(), 306);
+        assert_eq!(at(2024, 12, 25, 0, 0).weeks_remaining_in_year(), 0);
+    }
+
+    #[test]
+    fn remaining_time_on_spring_forward_day() {
+        // 01:30 on a 23-hour day leaves 21.5 hours, not 22.5
+        let tz = Tz::America__New_York;
+        let dt = tz.with_ymd_and_hms(2024, 3, 10, 1, 30, 0).unwrap();
+        let stats = compute_time_statistics(dt.fixed_offset(), Some(tz));
+        assert_eq!(stats.seconds_remaining_in_day(), 21 * 3600 + 1800);
+    }
+
+    #[test]
+    fn event_type_names_match_the_cli() {
+        for event_type in EventType::value_variants() {
+            let value = event_type.to_possible_value().unwrap();
+            assert_eq!(event_type.to_string(), value.get_name());
+        }
+        assert_eq!(EventType::Anniversaries.api_segment(), "all");
+        assert_eq!(EventType::Births.api_segment(), "births");
+    }
+
+    #[test]
+    fn cache_entry_freshness() {
+        let entry = CacheEntry {
+            fetched_at: 1_000,
+            etag: None,
+            last_modified: None,
+            body: "{}".into(),
+        };
+        assert!(entry.is_fresh(1_000 + CACHE_TTL_SECS - 1, CACHE_TTL_SECS));
+        assert!(!entry.is_fresh(1_000 + CACHE_TTL_SECS, CACHE_TTL_SECS));
+        assert!(!entry.is_fresh(1_000, 0));
+    }
+
+    fn client(policy: CachePolicy) -> WikiClient {
+        WikiClient::new(FetchOptions {
+            policy,
+            ..FetchOptions::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn stale_cache_entry_is_revalidated() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/feed")
+            .match_header("if-none-match", "\"v1\"")
+            .match_header("if-modified-since", "Sat, 01 Jun 2024 00:00:00 GMT")
+            .with_status(304)
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("entry.json");
+        let stale = CacheEntry {
+            fetched_at: 0,
+            etag: Some("\"v1\"".into()),
+            last_modified: Some("Sat, 01 Jun 2024 00:00:00 GMT".into()),
+            body: r#"{"events":[{"year":1969,"text":"cached"}]}"#.into(),
+        };
+        store_cache_entry(&path, &stale).unwrap();
+
+        let url = format!("{}/feed", server.url());
+        let loaded = client(CachePolicy::default())
+            .fetch_with_disk_cache::<OnThisDayResponse>(&url, Some(&path))
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(loaded.response.events[0].text, "cached");
+        assert!(loaded.metrics.cache_hit);
+        assert_eq!(loaded.metrics.bytes, 0); // a 304 has no body
+        assert_eq!(loaded.metrics.status, Some(StatusCode::NOT_MODIFIED));
+        let refreshed = load_cache_entry(&path).unwrap();
+        let now = chrono::Utc::now().timestamp();
+        assert!(refreshed.is_fresh(now, CACHE_TTL_SECS));
+    }
+
+    #[tokio::test]
+    async fn failures_are_not_cached() {
+        let mut server = mockito::Server::new_async().await;
+        let failing = server
+            .mock("GET", "/feed")
+            .with_status(503)
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("entry.json");
+        let url = format!("{}/feed", server.url());
+        let wiki = client(CachePolicy::default());
+        let failed = wiki
+            .fetch_with_disk_cache::<OnThisDayResponse>(&url, Some(&path))
+            .await;
+        assert!(failed.is_err());
+        assert!(load_cache_entry(&path).is_none());
+
+        // The retry goes back to the network instead of replaying the error
+        failing.remove_async().await;
+        let ok = server
+            .mock("GET", "/feed")
+            .with_body(r#"{"holidays":[{"text":"Moon Day"}]}"#)
+            .create_async()
+            .await;
+        let loaded = wiki
+            .fetch_with_disk_cache::<OnThisDayResponse>(&url, Some(&path))
+            .await
+            .unwrap();
+        ok.assert_async().await;
+        assert!(!loaded.metrics.cache_hit);
+        assert_eq!(loaded.response.holidays[0].text, "Moon Day");
+    }
+
+    #[tokio::test]
+    async fn stale_ok_serves_an_expired_entry_when_the_fetch_fails() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/feed")
+            .with_status(503)
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("entry.json");
+        let stale = CacheEntry {
+            fetched_at: 0,
+            etag: None,
+            last_modified: None,
+            body: r#"{"holidays":[{"text":"Moon Day"}]}"#.to_string(),
+        };
+        store_cache_entry(&path, &stale).unwrap();
+        let url = format!("{}/feed", server.url());
+
+        let policy = CachePolicy::default();
+        let wiki = client(policy);
+        let failed = wiki
+            .fetch_with_disk_cache::<OnThisDayResponse>(&url, Some(&path))
+            .await;
+        assert!(failed.is_err());
+
+        let policy = CachePolicy {
+            stale_ok: true,
+            ..policy
+        };
+        let loaded = client(policy)
+            .fetch_with_disk_cache::<OnThisDayResponse>(&url, Some(&path))
+            .await
+            .unwrap();
+        assert_eq!(loaded.response.holidays[0].text, "Moon Day");
+        assert_eq!(loaded.fetched_at, 0);
+        assert!(loaded.metrics.cache_hit);
+        assert!(loaded.refresh_error.unwrap().contains("503"));
+    }
+
+    #[tokio::test]
+    async fn fresh_response_is_stored_with_validators() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/feed")
+            .with_header("etag", "\"v2\"")
+            .with_body(r#"{"holidays":[{"text":"Moon Day"}]}"#)
+            .create_async()
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("entry.json");
+        let url = format!("{}/feed", server.url());
+        let loaded = client(CachePolicy::default())
+            .fetch_with_disk_cache::<OnThisDayResponse>(&url, Some(&path))
+            .await
+            .unwrap();
+
+        assert!(!loaded.metrics.cache_hit);
+        assert_eq!(loaded.metrics.status, Some(StatusCode::OK));
+        assert_eq!(loaded.response.holidays[0].text, "Moon Day");
+        let stored = load_cache_entry(&path).unwrap();
+        assert_eq!(stored.etag.as_deref(), Some("\"v2\""));
+    }
+
+    #[test]
+    fn event_url_from_first_linked_page() {
+        let ev: Event = serde_json::from_str(
+            r#"{"year": 1969, "text": "Apollo 11", "pages": [
+                {"title": "No URL"},
+                {"content_urls": {"desktop": {
+                    "page": "https://en.wikipedia.org/wiki/Apollo_11"
+                }}}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ev.url.as_deref(),
+            Some("https://en.wikipedia.org/wiki/Apollo_11"),
+        );
+
+        let bare: Event =
+            serde_json::from_str(r#"{"year": 1, "text": "x"}"#).unwrap();
+        assert!(bare.url.is_none());
+    }
+
+    #[test]
+    fn daylight_matches_published_times() {
+        // London on the 2024 June solstice: 04:43 to 21:21 BST
+        let date = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+        let Daylight::Span { rise, set } = daylight(date, 51.5074, -0.1278)
+        else {
+            panic!("no sunrise in London");
+        };
+        let utc = |h, m| date.and_hms_opt(h, m, 0).unwrap().and_utc();
+        assert!((rise - utc(3, 43)).num_seconds().abs() <= 120, "{rise}");
+        assert!((set - utc(20, 21)).num_seconds().abs() <= 120, "{set}");
+
+        // Sydney on the December solstice: 05:41 to 20:05 AEDT
+        let date = NaiveDate::from_ymd_opt(2024, 12, 21).unwrap();
+        let Daylight::Span { rise, set } = daylight(date, -33.8688, 151.2093)
+        else {
+            panic!("no sunrise in Sydney");
+        };
+        let utc = |d, h, m| {
+            NaiveDate::from_ymd_opt(2024, 12, d)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+                .and_utc()
+        };
+        assert!((rise - utc(20, 18, 41)).num_seconds().abs() <= 120, "{rise}");
+        assert!((set - utc(21, 9, 5)).num_seconds().abs() <= 120, "{set}");
+    }
+
+    #[test]
+    fn daylight_in_the_polar_circles() {
+        let (lat, lon) = (69.6492, 18.9553); // Tromsø
+        let day = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        assert_eq!(daylight(day(6, 21), lat, lon), Daylight::MidnightSun);
+        assert_eq!(daylight(day(12, 21), lat, lon), Daylight::PolarNight);
+        assert!(matches!(
+            daylight(day(3, 20), lat, lon),
+            Daylight::Span { .. }
+        ));
+    }
+
+    #[test]
+    fn feed_url_is_well_formed() {
+        let url = feed_url("https://en.wikipedia.org", "events", 7, 20);
+        assert_eq!(
+            url.unwrap().as_str(),
+            "https://en.wikipedia.org/api/rest_v1/feed/onthisday/events/7/20",
+        );
+        let url = feed_url("http://127.0.0.1:1234/en/", "a b/../c?d", 1, 2);
+        assert_eq!(
+            url.unwrap().as_str(),
+            "http://127.0.0.1:1234/en/api/rest_v1/feed/onthisday/\
+             a%20b%2F..%2Fc%3Fd/1/2",
+        );
+    }
+
+    #[test]
+    fn api_base_url_rejects_odd_languages() {
+        for lang in ["", "e n", "en/../x", "en.evil.com", "en?", "ü"] {
+            assert!(api_base_url(None, lang).is_err(), "accepted {lang:?}");
+        }
+        assert!(api_base_url(None, "zh-yue").is_ok());
+        let mock = api_base_url(Some("http://127.0.0.1:1/{lang}"), "de");
+        assert_eq!(mock.unwrap(), "http://127.0.0.1:1/de");
+    }
+}
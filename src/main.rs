@@ -1,35 +1,395 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{ErrorKind, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::time::{Duration, Instant};
 
-use anyhow::{bail, Result};
-use cached::proc_macro::cached;
-use chrono::{Datelike, Local, NaiveDate, Timelike};
-use clap::{Parser, Subcommand, ValueEnum};
+use anyhow::{anyhow, bail, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{
+    DateTime, Datelike, Days, FixedOffset, Local, Month, Months, NaiveDate,
+    TimeDelta, Utc,
+};
+use chrono_tz::Tz;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 use comfy_table::{
-    presets::UTF8_FULL, Attribute, Cell, Color, ContentArrangement, Row, Table,
+    presets, Attribute, Cell, CellAlignment, Color, ContentArrangement,
+    Table,
 };
 use indicatif::{ProgressBar, ProgressStyle};
-use once_cell::sync::Lazy;
-use owo_colors::OwoColorize;
-use reqwest::Client;
-use serde::Deserialize;
+use owo_colors::{AnsiColors, OwoColorize, Style};
+use schemars::generate::SchemaSettings;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use textwrap::{fill, termwidth};
+use time_cli::{
+    compute_time_statistics, daylight, CachePolicy, Daylight, Event,
+    EventType, FetchMetrics, FetchOptions, Fetched, FetchedImage, Holiday,
+    OnThisDayResponse, TimeStats, WikiClient, CACHE_TTL_SECS,
+};
 
 /* --------------------------------------------------------------------------
  *                                helpers
  * ---------------------------------------------------------------------- */
 
-/// Validate an ISO-639-1 language code (exactly two ASCII letters).
+/// Validate a Wikipedia language code: two or three ASCII letters
+/// (`de`, `nah`), optionally followed by hyphenated alphanumeric parts
+/// (`zh-yue`, `be-tarask`).
 fn parse_lang_code(s: &str) -> std::result::Result<String, String> {
-    if s.len() == 2 && s.chars().all(|c| c.is_ascii_alphabetic()) {
+    let mut parts = s.split('-');
+    let base = parts.next().unwrap_or_default();
+    let valid = (2..=3).contains(&base.len())
+        && base.chars().all(|c| c.is_ascii_alphabetic())
+        && parts.all(|part| {
+            !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    if valid {
         Ok(s.to_ascii_lowercase())
     } else {
         Err(format!(
-            "'{s}' is not a valid ISO-639-1 language code \
-             (two ASCII letters)",
+            "'{s}' is not a valid Wikipedia language code \
+             (two or three ASCII letters, e.g. `de`, `nah` or `zh-yue`)",
         ))
     }
 }
 
+/// Parse an ISO-8601 calendar date (`YYYY-MM-DD`).
+fn parse_iso_date(s: &str) -> std::result::Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map_err(|_| format!("'{s}' is not a valid date (expected YYYY-MM-DD)"))
+}
+
+/// Longest span `--range` accepts, to keep request counts polite.
+const MAX_RANGE_DAYS: i64 = 366;
+
+/// An inclusive span of calendar days, written `START..END`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct DateRange {
+    start: NaiveDate,
+    end: NaiveDate,
+}
+
+impl DateRange {
+    fn days(self) -> impl Iterator<Item = NaiveDate> {
+        self.start.iter_days().take_while(move |date| *date <= self.end)
+    }
+}
+
+fn parse_date_range(s: &str) -> std::result::Result<DateRange, String> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| format!("'{s}' is not a range (expected START..END)"))?;
+    let range = DateRange {
+        start: parse_iso_date(start)?,
+        end: parse_iso_date(end)?,
+    };
+    if range.end < range.start {
+        return Err(format!("range '{s}' ends before it starts"));
+    }
+    if (range.end - range.start).num_days() >= MAX_RANGE_DAYS {
+        return Err(format!(
+            "range '{s}' spans more than {MAX_RANGE_DAYS} days",
+        ));
+    }
+    Ok(range)
+}
+
+/// A calendar-aware amount of time: whole months (years count as 12),
+/// whole days (weeks count as 7) and a sub-day remainder in seconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct HumanDuration {
+    months: u32,
+    days: u64,
+    seconds: i64,
+}
+
+/// Parse durations like `1y2mo`, `3 weeks`, `1d 12h` or `90min`.
+fn parse_human_duration(
+    s: &str,
+) -> std::result::Result<HumanDuration, String> {
+    let invalid = || {
+        format!(
+            "'{s}' is not a valid duration (e.g. '1y2mo', '3 weeks', \
+             '1d12h'; units: y, mo, w, d, h, min, s)",
+        )
+    };
+
+    let mut duration = HumanDuration::default();
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let amount: u32 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = rest[digits..].trim_start();
+
+        let letters = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let unit = rest[..letters].to_ascii_lowercase();
+        rest = rest[letters..].trim_start_matches([' ', ',']);
+
+        let (months, days, seconds) = match unit.as_str() {
+            "y" | "yr" | "yrs" | "year" | "years" => (12, 0, 0),
+            "mo" | "mon" | "month" | "months" => (1, 0, 0),
+            "w" | "wk" | "wks" | "week" | "weeks" => (0, 7, 0),
+            "d" | "day" | "days" => (0, 1, 0),
+            "h" | "hr" | "hrs" | "hour" | "hours" => (0, 0, 3600),
+            "m" | "min" | "mins" | "minute" | "minutes" => (0, 0, 60),
+            "s" | "sec" | "secs" | "second" | "seconds" => (0, 0, 1),
+            _ => return Err(invalid()),
+        };
+        duration.months = duration
+            .months
+            .checked_add(amount.checked_mul(months).ok_or_else(invalid)?)
+            .ok_or_else(invalid)?;
+        duration.days += u64::from(amount) * days;
+        duration.seconds += i64::from(amount) * seconds;
+    }
+
+    Ok(duration)
+}
+
+/// A point in time given on the command line; remembers whether only a
+/// calendar date was given so the result can be printed the same way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Moment {
+    at: DateTime<FixedOffset>,
+    date_only: bool,
+}
+
+/// Parse `now`, `today`, `YYYY-MM-DD` or an RFC 3339 timestamp.
+fn parse_moment(s: &str) -> std::result::Result<Moment, String> {
+    let now = Local::now().fixed_offset();
+    let date_only = |date: NaiveDate| Moment {
+        at: date.and_time(Default::default()).and_utc().fixed_offset(),
+        date_only: true,
+    };
+
+    match s.to_ascii_lowercase().as_str() {
+        "now" => Ok(Moment {
+            at: now,
+            date_only: false,
+        }),
+        "today" => Ok(date_only(now.date_naive())),
+        _ => {
+            if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+                Ok(date_only(date))
+            } else {
+                DateTime::parse_from_rfc3339(s)
+                    .map(|at| Moment {
+                        at,
+                        date_only: false,
+                    })
+                    .map_err(|_| {
+                        format!(
+                            "'{s}' is not a valid date (expected 'now', \
+                             'today', YYYY-MM-DD or RFC 3339)",
+                        )
+                    })
+            }
+        }
+    }
+}
+
+/// Check a strftime format up front, naming the first specifier chrono
+/// doesn't understand; formatting with a bad one would panic mid-output.
+fn validate_format(fmt: &str) -> Result<()> {
+    let is_bad = |spec: &str| {
+        StrftimeItems::new(spec).any(|item| matches!(item, Item::Error))
+    };
+    if !is_bad(fmt) {
+        return Ok(());
+    }
+
+    // A specifier is `%`, optional padding/width/colon flags, one letter
+    let mut rest = fmt;
+    while let Some(start) = rest.find('%') {
+        let spec = &rest[start..];
+        let flags = spec[1..]
+            .find(|c: char| {
+                !matches!(c, '-' | '_' | '0'..='9' | '#' | ':' | '.')
+            })
+            .map_or(spec.len(), |i| i + 1);
+        let len = spec[flags..]
+            .chars()
+            .next()
+            .map_or(flags, |c| flags + c.len_utf8());
+        if is_bad(&spec[..len]) {
+            bail!("'{}' is not a valid strftime specifier", &spec[..len]);
+        }
+        rest = &spec[len..];
+    }
+    bail!("'{fmt}' is not a valid strftime format")
+}
+
+/// `value_parser` wrapper around [`validate_format`].
+fn parse_format(s: &str) -> std::result::Result<String, String> {
+    validate_format(s)
+        .map(|()| s.to_owned())
+        .map_err(|err| err.to_string())
+}
+
+/// Parse a clock `--format`: a [`ClockPreset`] name or a strftime format.
+fn parse_clock_format(s: &str) -> std::result::Result<ClockFormat, String> {
+    match ClockPreset::from_str(s, true) {
+        Ok(preset) => Ok(ClockFormat::Preset(preset)),
+        Err(_) => parse_format(s).map(ClockFormat::Strftime),
+    }
+}
+
+/// Parse a Unix timestamp (seconds) or anything [`parse_moment`] takes.
+fn parse_instant(s: &str) -> std::result::Result<Moment, String> {
+    match s.parse::<i64>() {
+        Ok(secs) => DateTime::from_timestamp(secs, 0)
+            .map(|at| Moment {
+                at: at.fixed_offset(),
+                date_only: false,
+            })
+            .ok_or_else(|| format!("timestamp {secs} is out of range")),
+        Err(_) => parse_moment(s),
+    }
+}
+
+/// Parse a month given as 1–12, an English name (`July`) or its
+/// three-letter abbreviation (`jul`), in any case.
+fn parse_month(s: &str) -> std::result::Result<u32, String> {
+    if let Ok(number) = s.parse::<u32>()
+        && (1..=12).contains(&number)
+    {
+        return Ok(number);
+    }
+    s.parse::<Month>()
+        .map(|month| month.number_from_month())
+        .map_err(|_| {
+            format!(
+                "'{s}' is not a month (expected 1-12, a name like July \
+                 or an abbreviation like jul)"
+            )
+        })
+}
+
+/// Parse a calendar day without a year (`MM-DD`); Feb-29 is allowed.
+fn parse_month_day(s: &str) -> std::result::Result<NaiveDate, String> {
+    // 2024 is a leap year, so every real MM-DD exists in it
+    NaiveDate::parse_from_str(&format!("2024-{s}"), "%Y-%m-%d")
+        .map_err(|_| format!("'{s}' is not a valid day (expected MM-DD)"))
+}
+
+/// Parse an IANA time zone name such as `Europe/Berlin`.
+fn parse_timezone(s: &str) -> std::result::Result<Tz, String> {
+    s.parse().map_err(|_| {
+        format!("'{s}' is not a known IANA time zone (e.g. Europe/Berlin)")
+    })
+}
+
+/// Parse a latitude in degrees, -90 to 90 (north positive).
+fn parse_latitude(s: &str) -> std::result::Result<f64, String> {
+    parse_degrees(s, 90.0)
+}
+
+/// Parse a longitude in degrees, -180 to 180 (east positive).
+fn parse_longitude(s: &str) -> std::result::Result<f64, String> {
+    parse_degrees(s, 180.0)
+}
+
+/// Parse a `--separator`, turning the escapes `\t`, `\n`, `\0` and `\\`
+/// into the characters they name (a shell can't pass a NUL byte as is).
+fn parse_separator(s: &str) -> std::result::Result<String, String> {
+    let mut separator = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            separator.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => separator.push('\t'),
+            Some('n') => separator.push('\n'),
+            Some('0') => separator.push('\0'),
+            Some('\\') | None => separator.push('\\'),
+            Some(other) => {
+                return Err(format!(
+                    "unknown escape '\\{other}' in '{s}' (expected \\t, \\n, \
+                     \\0 or \\\\)"
+                ));
+            }
+        }
+    }
+    if separator.is_empty() {
+        return Err("the separator can't be empty".to_string());
+    }
+    Ok(separator)
+}
+
+fn parse_degrees(s: &str, limit: f64) -> std::result::Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(deg) if deg.abs() <= limit => Ok(deg),
+        _ => Err(format!("'{s}' is not a number of degrees in ±{limit}")),
+    }
+}
+
+/// Languages that write decimals with a comma (`16,7`); the rest of the
+/// world's `--locale`s get a point.
+const DECIMAL_COMMA_LANGUAGES: &[&str] = &[
+    "bg", "ca", "cs", "da", "de", "el", "es", "et", "eu", "fi", "fr", "gl",
+    "hr", "hu", "id", "is", "it", "lt", "lv", "nb", "nl", "nn", "no", "pl",
+    "pt", "ro", "ru", "sk", "sl", "sr", "sv", "tr", "uk", "vi",
+];
+
+/// The number conventions of a `--locale`; only the decimal mark matters
+/// for what the statistics print.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct NumberLocale {
+    decimal: char,
+}
+
+impl NumberLocale {
+    /// Swaps the point in an already formatted number for this locale's
+    /// decimal mark.
+    fn number(self, formatted: String) -> String {
+        if self.decimal == '.' {
+            formatted
+        } else {
+            formatted.replace('.', &self.decimal.to_string())
+        }
+    }
+}
+
+impl Default for NumberLocale {
+    fn default() -> Self {
+        NumberLocale { decimal: '.' }
+    }
+}
+
+/// `de`, `de_DE`, `de-AT` or `de_DE.UTF-8`: the language decides.
+fn parse_locale(s: &str) -> std::result::Result<NumberLocale, String> {
+    let language = s
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if !(2..=3).contains(&language.len())
+        || !language.chars().all(|c| c.is_ascii_alphabetic())
+    {
+        return Err(format!(
+            "'{s}' is not a locale (expected e.g. en, de or de_DE)"
+        ));
+    }
+    let decimal = if DECIMAL_COMMA_LANGUAGES.contains(&language.as_str()) {
+        ','
+    } else {
+        '.'
+    };
+    Ok(NumberLocale { decimal })
+}
+
 /* --------------------------------------------------------------------------
  *                                  CLI
  * ---------------------------------------------------------------------- */
@@ -52,379 +412,3754 @@ struct Cli {
     /// Also show progress through the day / year
     #[arg(short, long)]
     statistics: bool,
-}
 
-#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Hash)]
-#[value(rename_all = "lower")]
-enum EventType {
-    Events,
-    Births,
-    Deaths,
-    Holidays,
-}
+    /// Print the statistics on two dense lines (for status bars)
+    #[arg(long, requires = "statistics")]
+    compact: bool,
 
-#[derive(Subcommand, Debug)]
-enum Command {
-    /// Fetch “On This Day” events from Wikipedia
-    History(HistoryArgs),
-}
+    /// Print the statistics as a JSON object instead
+    #[arg(long, requires = "statistics", conflicts_with = "compact")]
+    json: bool,
 
-#[derive(Parser, Debug)]
-struct HistoryArgs {
-    /// Type of events to show
+    /// Print the statistics in Prometheus text exposition format (for
+    /// node_exporter's textfile collector)
+    #[arg(
+        long,
+        requires = "statistics",
+        conflicts_with_all = ["compact", "json", "explain"],
+    )]
+    prometheus: bool,
+
+    /// Follow each statistics line with a short note on what it means
+    #[arg(
+        long,
+        requires = "statistics",
+        conflicts_with_all = ["compact", "json"],
+    )]
+    explain: bool,
+
+    /// Add a bar for how far through the week (Monday to Sunday) it is
+    #[arg(
+        long,
+        requires = "statistics",
+        conflicts_with_all = ["compact", "json", "prometheus"],
+    )]
+    detailed: bool,
+
+    /// Characters for the statistics progress bars
     #[arg(
-        short = 't',
         long,
         value_enum,
-        default_value_t = EventType::Events,
-        value_name = "TYPE",
+        value_name = "STYLE",
+        default_value_t = BarStyle::Blocks,
     )]
-    r#type: EventType,
+    bar_style: BarStyle,
 
-    /// Wikipedia language code
+    /// Colour of the filled part of the progress bars
     #[arg(
-        short,
         long,
-        value_parser = parse_lang_code,
-        value_name = "LANG",
-        default_value = "en",
+        value_enum,
+        value_name = "COLOR",
+        default_value_t = BarColor::Green,
     )]
-    language: String,
+    bar_color: BarColor,
 
-    /// Suppress the spinner (useful for scripts)
-    #[arg(long)]
-    quiet: bool,
+    /// Decimal places in the statistics progress percentages
+    #[arg(
+        long,
+        value_name = "DIGITS",
+        value_parser = clap::value_parser!(u8).range(0..=2),
+        default_value_t = 1,
+    )]
+    progress_precision: u8,
 
-    /// Override month (1-12). Defaults to the current month.
+    /// Locale for the numbers in the statistics, e.g. `de` or `de_DE` for
+    /// `16,7 %`; JSON and Prometheus output always use a point
+    #[arg(long, value_name = "LOCALE", value_parser = parse_locale)]
+    locale: Option<NumberLocale>,
+
+    /// Print the current time as `human` (the default two-line layout),
+    /// `iso`, `rfc2822`, `unix` or `short`, or with a strftime format such
+    /// as `%H:%M`
     #[arg(
-        short = 'm',
         long,
-        value_name = "MONTH",
-        value_parser = clap::value_parser!(u32).range(1..=12),
+        value_name = "FORMAT",
+        value_parser = parse_clock_format,
+        conflicts_with = "statistics",
     )]
-    month: Option<u32>,
+    format: Option<ClockFormat>,
 
-    /// Override day of the month (1-31). Defaults to the current day.
+    /// Keep redrawing the clock (or the statistics) in place until Ctrl-C
+    #[arg(long, conflicts_with_all = ["json", "prometheus"])]
+    watch: bool,
+
+    /// Milliseconds between `--watch` redraws (at least 100)
     #[arg(
-        short = 'd',
         long,
-        value_name = "DAY",
-        value_parser = clap::value_parser!(u32).range(1..=31),
+        value_name = "MS",
+        default_value_t = 1000,
+        value_parser = clap::value_parser!(u64).range(100..),
+        requires = "watch",
     )]
-    day: Option<u32>,
-}
+    interval: u64,
 
-/* --------------------------------------------------------------------------
- *                                models
- * ---------------------------------------------------------------------- */
+    /// Latitude for a sunrise/sunset line in the statistics, in degrees
+    /// (north positive)
+    #[arg(
+        long,
+        value_name = "DEG",
+        value_parser = parse_latitude,
+        allow_negative_numbers = true,
+        requires_all = ["lon", "statistics"],
+        conflicts_with_all = ["compact", "json", "prometheus"],
+    )]
+    lat: Option<f64>,
 
-#[derive(Deserialize, Debug, Clone)]
-struct OnThisDayResponse {
-    #[serde(default)]
-    events: Vec<Event>,
-    #[serde(default)]
-    births: Vec<Event>,
-    #[serde(default)]
-    deaths: Vec<Event>,
-    #[serde(default)]
-    holidays: Vec<Holiday>,
-}
+    /// Longitude to go with `--lat`, in degrees (east positive)
+    #[arg(
+        long,
+        value_name = "DEG",
+        value_parser = parse_longitude,
+        allow_negative_numbers = true,
+        requires = "lat",
+    )]
+    lon: Option<f64>,
 
-#[derive(Deserialize, Debug, Clone)]
-struct Event {
-    year: i32,
-    text: String,
-}
+    /// Show the full chain of causes when something goes wrong, log each
+    /// request's size and round-trip time, and sum up `history` runs
+    #[arg(short, long, global = true)]
+    verbose: bool,
 
-#[derive(Deserialize, Debug, Clone)]
-struct Holiday {
-    text: String,
-}
+    /// User-Agent for Wikipedia requests (defaults to name/version plus
+    /// the project URL)
+    #[arg(
+        long,
+        global = true,
+        value_name = "STRING",
+        env = "TIME_CLI_USER_AGENT",
+    )]
+    user_agent: Option<String>,
 
-/* --------------------------------------------------------------------------
- *                                globals
- * ---------------------------------------------------------------------- */
+    /// Print the URL of each request sent to Wikipedia to stderr
+    #[arg(long, global = true)]
+    show_url: bool,
 
-static CLIENT: Lazy<Client> = Lazy::new(|| {
-    Client::builder()
-        .user_agent(concat!(
-        env!("CARGO_PKG_NAME"),
-        '/',
-        env!("CARGO_PKG_VERSION")
-        ))
-        .timeout(Duration::from_secs(10))
-        .build()
-        .expect("Failed to build HTTP client")
-});
+    /// Print `cache: hit <url>` or `cache: miss <url>` to stderr for each
+    /// fetch (a revalidated copy counts as a hit)
+    #[arg(long, global = true)]
+    show_cache_status: bool,
 
-/* --------------------------------------------------------------------------
- *                                 main
- * ---------------------------------------------------------------------- */
+    /// Use UTC instead of the local time zone (also picks history's day)
+    #[arg(long, global = true, conflicts_with = "timezone")]
+    utc: bool,
 
-#[tokio::main(flavor = "current_thread")]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    /// Use this IANA time zone instead of the local one, e.g. `Asia/Tokyo`
+    /// (also picks history's day)
+    #[arg(
+        long,
+        global = true,
+        value_name = "TZ",
+        value_parser = parse_timezone,
+    )]
+    timezone: Option<Tz>,
 
-    match cli.command {
-        Some(Command::History(args)) => {
-            let start = Instant::now();
-            show_on_this_day(&args).await?;
-            println!("\nFinished in {:.2?}.", start.elapsed());
-        }
-        None => {
-            let now = Local::now();
-            if cli.statistics {
-                show_time_statistics(now);
-            } else {
-                show_current_time(now);
-            }
-        }
-    }
+    /// Don't colour the history tables or the progress bars (same as
+    /// `--theme mono --bar-color none`); a non-empty `NO_COLOR` does too
+    #[arg(long, global = true)]
+    no_color: bool,
 
-    Ok(())
+    /// Print the current time (and `add`/`sub` results) with this strftime
+    /// format, e.g. `%Y-%m-%d %H:%M:%S%.3f`; overrides `--format` and
+    /// `--time-format` when given. Not with `--statistics`
+    #[arg(
+        long,
+        global = true,
+        value_name = "STRFTIME",
+        value_parser = parse_format,
+    )]
+    date_fmt: Option<String>,
 }
 
-/* --------------------------------------------------------------------------
- *                              Wikipedia
- * ---------------------------------------------------------------------- */
+impl Cli {
+    /// How to print the clock: `--date-fmt` when given, else `format`.
+    fn clock_format(
+        &self,
+        format: Option<&ClockFormat>,
+    ) -> Option<ClockFormat> {
+        match &self.date_fmt {
+            Some(fmt) => Some(ClockFormat::Strftime(fmt.clone())),
+            None => format.cloned(),
+        }
+    }
 
-#[cached(
-    time = 86400, // Cache for 24 hours
-    result = true  // Cache both Ok and Err results
-)]
-async fn fetch_wikipedia_data(
-    lang: String,
-    event_type: String,
-    month: u32,
-    day: u32,
-) -> Result<OnThisDayResponse> {
-    // Allow overriding the API endpoint for testing purposes
-    let base_url = std::env::var("TEST_WIKIPEDIA_API_URL").unwrap_or_else(
-        |_| format!("https://{lang}.wikipedia.org"),
-    );
+    /// The zone chosen by `--utc` / `--timezone`; `None` means local.
+    fn zone(&self) -> Option<Tz> {
+        if self.utc { Some(Tz::UTC) } else { self.timezone }
+    }
+}
 
-    let url = format!(
-        "{base_url}/api/rest_v1/feed/onthisday/{event_type}/{month}/{day}",
-    );
+/// Row orders for `--sort`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[value(rename_all = "lower")]
+enum SortKey {
+    /// Oldest first, for feeds that arrive out of order
+    Year,
+    /// Alphabetically by text; holidays only, as they have no year
+    Text,
+}
 
-    CLIENT
-        .get(url)
-        .send()
-        .await?
-        .error_for_status()?
-        .json()
-        .await
-        .map_err(Into::into)
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[value(rename_all = "lower")]
+enum Column {
+    Year,
+    #[value(alias = "text")]
+    Event,
 }
 
-async fn show_on_this_day(args: &HistoryArgs) -> Result<()> {
-    // Determine the requested calendar day
-    let today = Local::now();
-    let month = args.month.unwrap_or(today.month());
-    let day = args.day.unwrap_or(today.day());
+/// Border style of the history tables, one per comfy-table preset.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[value(rename_all = "kebab-case")]
+enum TablePreset {
+    /// Box-drawing borders and row separators
+    Full,
+    /// Box-drawing borders without row separators
+    Condensed,
+    /// Only the outer frame and the header line
+    BordersOnly,
+    /// Only horizontal lines
+    HorizontalOnly,
+    /// `+`, `-` and `|` borders for plain-ASCII terminals
+    Ascii,
+    /// A Markdown pipe table
+    Markdown,
+    /// No borders at all
+    None,
+}
 
-    // Validate the month/day combination (use leap year for “Feb-29”)
-    if NaiveDate::from_ymd_opt(2024, month, day).is_none() {
-        bail!("'{month:02}-{day:02}' is not a valid calendar date");
+impl TablePreset {
+    fn preset(self) -> &'static str {
+        match self {
+            TablePreset::Full => presets::UTF8_FULL,
+            TablePreset::Condensed => presets::UTF8_FULL_CONDENSED,
+            TablePreset::BordersOnly => presets::UTF8_BORDERS_ONLY,
+            TablePreset::HorizontalOnly => presets::UTF8_HORIZONTAL_ONLY,
+            TablePreset::Ascii => presets::ASCII_FULL,
+            TablePreset::Markdown => presets::ASCII_MARKDOWN,
+            TablePreset::None => presets::NOTHING,
+        }
     }
+}
 
-    let event_type_name =
-        args.r#type.to_possible_value().unwrap().get_name().to_string();
+/// Horizontal alignment of the entry text in tables.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[value(rename_all = "lower")]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
 
-    // Optional spinner
-    let spinner = if args.quiet {
-        None
-    } else {
-        let pb = ProgressBar::new_spinner();
-        pb.enable_steady_tick(Duration::from_millis(120));
-        pb.set_style(
-            ProgressStyle::with_template("{spinner:.blue} {msg}")?.tick_strings(
-                &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
-            ),
-        );
-        pb.set_message(format!(
-            "Fetching {event_type} for {month:02}-{day:02} ({lang})",
-            event_type = &event_type_name,
-            lang = &args.language,
-        ));
-        Some(pb)
-    };
+impl Align {
+    fn cell_alignment(self) -> CellAlignment {
+        match self {
+            Align::Left => CellAlignment::Left,
+            Align::Center => CellAlignment::Center,
+            Align::Right => CellAlignment::Right,
+        }
+    }
+}
 
-    // Fetch & parse JSON
-    let response = fetch_wikipedia_data(
-        args.language.clone(),
-        event_type_name,
-        month,
-        day,
-    )
-        .await?;
+/// A key of the entry objects in `--json` output.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[value(rename_all = "lower")]
+enum Field {
+    Year,
+    Text,
+    Url,
+}
 
-    if let Some(pb) = spinner {
-        pb.finish_and_clear();
+impl Field {
+    fn key(self) -> &'static str {
+        match self {
+            Field::Year => "year",
+            Field::Text => "text",
+            Field::Url => "url",
+        }
     }
+}
 
-    /* ----------- pretty table ----------- */
-    let mut table = Table::new();
-    table
-        .load_preset(UTF8_FULL)
-        .set_content_arrangement(ContentArrangement::Dynamic);
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[value(rename_all = "kebab-case")]
+enum ThemeName {
+    /// Bold headers, each category in its own colour
+    Default,
+    /// No colours or text attributes at all
+    Mono,
+    /// Bright white years and headers for dim terminals
+    #[value(alias = "highcontrast")]
+    HighContrast,
+    /// Solarized yellow years and blue headers
+    Solarized,
+}
 
-    let width = termwidth().max(50); // sensible minimum
+/// How the history output is coloured and emphasised.
+#[derive(Clone, Copy, Debug)]
+struct Theme {
+    year: Option<Color>,
+    header: Option<Color>,
+    /// Per-category colour for a section's header and years, taking
+    /// precedence over `year` and `header`
+    accents: Accents,
+    /// Bold table headers and years
+    bold: bool,
+    /// Bold/underlined day header and section titles
+    decorate: bool,
+    /// Filled part of the `--histogram`/`--count-by-type` bars
+    bar: BarColor,
+}
 
-    match args.r#type {
-        EventType::Holidays => {
-            table.set_header(vec![Cell::new("Holidays & Observances")
-                .add_attribute(Attribute::Bold)]);
-            if response.holidays.is_empty() {
-                table.add_row(vec!["No holidays found for this day."]);
-            } else {
-                for holiday in &response.holidays {
-                    table.add_row(vec![Cell::new(fill(
-                        &holiday.text,
-                        width - 5,
-                    ))]);
+impl ThemeName {
+    fn theme(self) -> Theme {
+        match self {
+            ThemeName::Default => Theme {
+                year: Some(Color::Yellow),
+                header: None,
+                accents: Accents {
+                    events: Some(Color::Yellow),
+                    births: Some(Color::Green),
+                    deaths: Some(Color::Red),
+                    holidays: Some(Color::Cyan),
+                },
+                bold: true,
+                decorate: true,
+                bar: BarColor::Green,
+            },
+            ThemeName::Mono => Theme {
+                year: None,
+                header: None,
+                accents: Accents::default(),
+                bold: false,
+                decorate: false,
+                bar: BarColor::None,
+            },
+            ThemeName::HighContrast => Theme {
+                year: Some(Color::White),
+                header: Some(Color::White),
+                accents: Accents::default(),
+                bold: true,
+                decorate: true,
+                bar: BarColor::White,
+            },
+            ThemeName::Solarized => {
+                let rgb = |r, g, b| Some(Color::Rgb { r, g, b });
+                Theme {
+                    year: rgb(0xb5, 0x89, 0x00),
+                    header: rgb(0x26, 0x8b, 0xd2),
+                    accents: Accents {
+                        events: rgb(0xb5, 0x89, 0x00),
+                        births: rgb(0x85, 0x99, 0x00),
+                        deaths: rgb(0xdc, 0x32, 0x2f),
+                        holidays: rgb(0x2a, 0xa1, 0x98),
+                    },
+                    bold: true,
+                    decorate: true,
+                    bar: BarColor::Yellow,
                 }
             }
         }
-        _ => {
-            let (header1, header2, events) = match args.r#type {
-                EventType::Events => ("Year", "Event", &response.events),
-                EventType::Births => ("Born", "Person", &response.births),
-                EventType::Deaths => ("Died", "Person", &response.deaths),
-                EventType::Holidays => unreachable!(),
-            };
-
-            table.set_header(vec![
-                Cell::new(header1).add_attribute(Attribute::Bold),
-                Cell::new(header2).add_attribute(Attribute::Bold),
-            ]);
+    }
+}
 
-            if events.is_empty() {
-                table.add_row(vec![
-                    Cell::new("N/A"),
-                    Cell::new("No entries of this type found for this day."),
-                ]);
-            } else {
-                for ev in events.iter().rev() {
-                    table.add_row(Row::from(vec![
-                        Cell::new(ev.year)
-                            .fg(Color::Yellow)
-                            .add_attribute(Attribute::Bold),
-                        Cell::new(fill(&ev.text, width - 15)),
-                    ]));
-                }
-            }
+impl Theme {
+    /// Apply a colour and/or bold to `cell`.
+    fn style(cell: Cell, color: Option<Color>, bold: bool) -> Cell {
+        let cell = match color {
+            Some(color) => cell.fg(color),
+            None => cell,
+        };
+        if bold {
+            cell.add_attribute(Attribute::Bold)
+        } else {
+            cell
         }
     }
 
-    // Nice human-readable header for the requested day
-    let fake_year = 2024; // leap year → Feb-29 always valid
-    let header_date = NaiveDate::from_ymd_opt(fake_year, month, day).unwrap();
-    println!(
-        "{} {}\n",
-        "— On This Day:".bold().underline(),
-        header_date.format("%B %e").to_string().trim(),
-    );
-    println!("{table}");
+    fn header_cell(&self, section: EventType, text: &str) -> Cell {
+        let color = self.accents.of(section).or(self.header);
+        Theme::style(Cell::new(text), color, self.bold)
+    }
 
-    Ok(())
+    fn year_cell(&self, section: EventType, year: i32) -> Cell {
+        let color = self.accents.of(section).or(self.year);
+        Theme::style(Cell::new(year), color, self.bold)
+    }
 }
 
-/* --------------------------------------------------------------------------
- *                              time output
- * ---------------------------------------------------------------------- */
+/// A theme's colour for each category; `None` falls back to the theme's
+/// general header/year colour.
+#[derive(Clone, Copy, Debug, Default)]
+struct Accents {
+    events: Option<Color>,
+    births: Option<Color>,
+    deaths: Option<Color>,
+    holidays: Option<Color>,
+}
 
-fn show_current_time(now: chrono::DateTime<Local>) {
-    println!(
-        "{}\n{}",
-        "The current time is:".bold(),
-        now.format("%A, %B %d, %Y %r"),
-    );
+impl Accents {
+    fn of(&self, section: EventType) -> Option<Color> {
+        match section {
+            EventType::Events
+            | EventType::Selected
+            | EventType::Anniversaries => self.events,
+            EventType::Births => self.births,
+            EventType::Deaths => self.deaths,
+            EventType::Holidays => self.holidays,
+            EventType::All | EventType::Featured => None,
+        }
+    }
 }
 
-fn ascii_bar(percent: f64, width: usize) -> String {
-    let filled = ((percent / 100.0) * width as f64).round() as usize;
-    let empty = width.saturating_sub(filled);
-    format!(
-        "{}{}",
-        "█".repeat(filled).green(),
-        "░".repeat(empty).dimmed(),
-    )
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[value(rename_all = "lower")]
+enum FeedFormat {
+    Rss,
+    Atom,
 }
 
-/* --------------------------------------------------------------------------
+/// Named clock formats, so common layouts don't need strftime.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[value(rename_all = "lower")]
+enum ClockPreset {
+    /// The default two-line layout
+    Human,
+    /// RFC 3339, e.g. `2024-03-01T12:00:00+00:00`
+    Iso,
+    /// RFC 2822, e.g. `Fri, 1 Mar 2024 12:00:00 +0000`
+    Rfc2822,
+    /// Seconds since 1970-01-01 00:00 UTC
+    Unix,
+    /// `%Y-%m-%d %H:%M`
+    Short,
+}
+
+/// A clock `--format`: a named preset or a (validated) strftime format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ClockFormat {
+    Preset(ClockPreset),
+    Strftime(String),
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[value(rename_all = "lower")]
+enum BarStyle {
+    /// `█` filled, `░` empty
+    Blocks,
+    /// `#` filled, `-` empty, for terminals without good Unicode
+    Ascii,
+    /// `▶` filled, `·` empty
+    Arrows,
+}
+
+impl BarStyle {
+    /// The (filled, empty) characters of this style.
+    fn chars(self) -> (char, char) {
+        match self {
+            BarStyle::Blocks => ('█', '░'),
+            BarStyle::Ascii => ('#', '-'),
+            BarStyle::Arrows => ('▶', '·'),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[value(rename_all = "lower")]
+enum BarColor {
+    Green,
+    Blue,
+    Cyan,
+    Yellow,
+    Magenta,
+    Red,
+    White,
+    /// Leave the filled part uncoloured
+    None,
+}
+
+impl BarColor {
+    fn ansi(self) -> Option<AnsiColors> {
+        match self {
+            BarColor::Green => Some(AnsiColors::Green),
+            BarColor::Blue => Some(AnsiColors::Blue),
+            BarColor::Cyan => Some(AnsiColors::Cyan),
+            BarColor::Yellow => Some(AnsiColors::Yellow),
+            BarColor::Magenta => Some(AnsiColors::Magenta),
+            BarColor::Red => Some(AnsiColors::Red),
+            BarColor::White => Some(AnsiColors::White),
+            BarColor::None => None,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the current time (what running without a subcommand does)
+    Now(NowArgs),
+
+    /// Fetch “On This Day” events from Wikipedia
+    History(Box<HistoryArgs>),
+
+    /// Morning dashboard: the time, statistics and a few of today's events
+    Today(TodayArgs),
+
+    /// Compare how many entries two days have, e.g. `diff 07-04 07-14`
+    Diff(DiffArgs),
+
+    /// Add a duration to a date, e.g. `add 2024-01-31 1mo`
+    Add(ShiftArgs),
+
+    /// Subtract a duration from a date, e.g. `sub now 2w`
+    Sub(ShiftArgs),
+
+    /// Say how long ago (or how far ahead) a moment is, e.g. `ago 1700000000`
+    Ago(AgoArgs),
+
+    /// Count the days until a yearly date comes round, e.g. `countdown 12-25`
+    Countdown(CountdownArgs),
+
+    /// Bookmark days to revisit with `history --favorite NAME`
+    Favorites(FavoritesArgs),
+
+    /// Print a shell completion script to stdout
+    Completions(CompletionsArgs),
+
+    /// Generate roff man pages for packaging
+    #[command(hide = true)]
+    Man(ManArgs),
+
+    /// Print the JSON Schema of a `--json` output
+    #[command(hide = true)]
+    Schema(SchemaArgs),
+}
+
+#[derive(Parser, Debug)]
+struct ShiftArgs {
+    /// Starting point: `now`, `today`, YYYY-MM-DD or RFC 3339
+    #[arg(value_name = "DATE", value_parser = parse_moment)]
+    date: Moment,
+
+    /// Amount to shift by, e.g. `1y2mo`, `3 weeks`, `1d12h`
+    #[arg(value_name = "DURATION", value_parser = parse_human_duration)]
+    duration: HumanDuration,
+
+    /// Print the result with this strftime format instead of the default
+    #[arg(long, value_name = "STRFTIME", value_parser = parse_format)]
+    format: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+struct NowArgs {
+    /// Print the time as `human` (the default two-line layout), `iso`,
+    /// `rfc2822`, `unix` or `short`, or with a strftime format such as
+    /// `%H:%M`
+    #[arg(
+        long,
+        visible_alias = "time-format",
+        value_name = "FORMAT",
+        value_parser = parse_clock_format,
+    )]
+    format: Option<ClockFormat>,
+
+    /// Print the time as a JSON object
+    #[arg(long, conflicts_with = "format")]
+    json: bool,
+}
+
+#[derive(Parser, Debug)]
+struct TodayArgs {
+    /// How many of today's events to list
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 5,
+        value_parser = clap::value_parser!(u16).range(1..),
+    )]
+    limit: u16,
+
+    /// Wikipedia language code
+    #[arg(
+        short,
+        long,
+        value_parser = parse_lang_code,
+        value_name = "LANG",
+        default_value = "en",
+    )]
+    language: String,
+
+    /// Colour theme for the On This Day part
+    #[arg(
+        long,
+        value_enum,
+        value_name = "THEME",
+        default_value_t = ThemeName::Default,
+    )]
+    theme: ThemeName,
+}
+
+#[derive(Parser, Debug)]
+struct DiffArgs {
+    /// First day (MM-DD)
+    #[arg(value_name = "MM-DD", value_parser = parse_month_day)]
+    first: NaiveDate,
+
+    /// Second day (MM-DD)
+    #[arg(value_name = "MM-DD", value_parser = parse_month_day)]
+    second: NaiveDate,
+
+    /// Wikipedia language code
+    #[arg(
+        short,
+        long,
+        value_parser = parse_lang_code,
+        value_name = "LANG",
+        default_value = "en",
+    )]
+    language: String,
+}
+
+#[derive(Parser, Debug)]
+struct AgoArgs {
+    /// Unix timestamp, `now`, `today`, YYYY-MM-DD or RFC 3339
+    #[arg(value_name = "TIMESTAMP", value_parser = parse_instant)]
+    timestamp: Moment,
+}
+
+#[derive(Parser, Debug)]
+struct CountdownArgs {
+    /// The recurring date (MM-DD), e.g. a birthday
+    #[arg(value_name = "MM-DD", value_parser = parse_month_day)]
+    date: NaiveDate,
+
+    /// Where Feb 29 falls in years without one
+    #[arg(
+        long,
+        value_enum,
+        value_name = "DAY",
+        default_value_t = LeapDay::Feb28,
+    )]
+    leap_day: LeapDay,
+}
+
+#[derive(Parser, Debug)]
+struct FavoritesArgs {
+    #[command(subcommand)]
+    action: FavoritesAction,
+}
+
+#[derive(Subcommand, Debug)]
+enum FavoritesAction {
+    /// Bookmark a day, e.g. `favorites add 07-20 --name moon-landing`
+    Add(FavoriteAddArgs),
+
+    /// List the bookmarked days in calendar order
+    List,
+
+    /// Forget a bookmark by name, or every bookmark on a day (MM-DD)
+    Remove(FavoriteRemoveArgs),
+}
+
+#[derive(Parser, Debug)]
+struct FavoriteAddArgs {
+    /// The day to bookmark (MM-DD)
+    #[arg(value_name = "MM-DD", value_parser = parse_month_day)]
+    date: NaiveDate,
+
+    /// Name to look the day up by (defaults to the MM-DD itself)
+    #[arg(long, value_name = "NAME")]
+    name: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+struct FavoriteRemoveArgs {
+    /// A bookmark's name, or a day (MM-DD) to drop all bookmarks on
+    #[arg(value_name = "NAME|MM-DD")]
+    target: String,
+}
+
+#[derive(Parser, Debug)]
+struct CompletionsArgs {
+    /// Shell to generate completions for
+    #[arg(value_enum, value_name = "SHELL")]
+    shell: Shell,
+}
+
+#[derive(Parser, Debug)]
+struct ManArgs {
+    /// Write one page per command into this directory instead of stdout
+    #[arg(short, long, value_name = "DIR")]
+    out_dir: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+struct SchemaArgs {
+    /// The output to describe
+    #[arg(value_enum)]
+    output: SchemaOutput,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[value(rename_all = "lower")]
+enum SchemaOutput {
+    /// `history --json` (without `--fields`, which drops keys)
+    History,
+    /// `--statistics --json`
+    Statistics,
+}
+
+#[derive(Parser, Debug)]
+struct HistoryArgs {
+    /// Type of events to show; pick several with a comma-separated list
+    /// (e.g. `events,births`), which shows those sections in that order
+    #[arg(
+        short = 't',
+        long = "type",
+        value_enum,
+        value_delimiter = ',',
+        default_value = "events",
+        value_name = "TYPE",
+    )]
+    types: Vec<EventType>,
+
+    /// Wikipedia language code
+    #[arg(
+        short,
+        long,
+        value_parser = parse_lang_code,
+        value_name = "LANG",
+        default_value = "en",
+    )]
+    language: String,
+
+    /// Languages to try in order when LANG has no entries (or no feed),
+    /// e.g. `-l ht --fallback fr,en`
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = parse_lang_code,
+        value_name = "LANG,...",
+    )]
+    fallback: Vec<String>,
+
+    /// Suppress the spinner; headers and the timing line still print
+    #[arg(long)]
+    quiet: bool,
+
+    /// Print only the data: no spinner, day/section headers or timing line
+    /// (errors still go to stderr)
+    #[arg(long)]
+    silent: bool,
+
+    /// Leave out the “— On This Day: …” line above each day, but keep
+    /// everything else (lighter than `--silent`)
+    #[arg(long)]
+    no_header: bool,
+
+    /// Don't print the “Finished in …” line after the output
+    #[arg(long)]
+    no_timing: bool,
+
+    /// Answer only from the disk cache, however old; never go online and
+    /// fail for days that were never fetched
+    #[arg(long)]
+    offline: bool,
+
+    /// Reuse cached responses (memory and disk) for up to this many
+    /// seconds; 0 neither reads nor writes either cache
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = CACHE_TTL_SECS,
+        value_parser = clap::value_parser!(i64).range(0..),
+        conflicts_with = "offline",
+    )]
+    cache_ttl: i64,
+
+    /// Ignore both caches and fetch fresh, then store the result in both
+    /// as usual
+    #[arg(long, conflicts_with = "offline")]
+    refresh: bool,
+
+    /// When a fetch fails, show the cached copy however old (with a
+    /// warning on stderr) instead of erroring out
+    #[arg(long, conflicts_with = "offline")]
+    stale_ok: bool,
+
+    /// Override month (1-12, `July` or `jul`). Defaults to the current
+    /// month.
+    #[arg(
+        short = 'm',
+        long,
+        conflicts_with_all = ["dates", "range", "week"],
+        value_name = "MONTH",
+        value_parser = parse_month,
+    )]
+    month: Option<u32>,
+
+    /// Override day of the month (1-31). Defaults to the current day.
+    #[arg(
+        short = 'd',
+        long,
+        conflicts_with_all = ["dates", "range", "week"],
+        value_name = "DAY",
+        value_parser = clap::value_parser!(u32).range(1..=31),
+    )]
+    day: Option<u32>,
+
+    /// Show this date (YYYY-MM-DD); repeat for several days
+    #[arg(long = "date", value_name = "DATE", value_parser = parse_iso_date)]
+    dates: Vec<NaiveDate>,
+
+    /// Show every day in an inclusive range (YYYY-MM-DD..YYYY-MM-DD)
+    #[arg(long, value_name = "START..END", value_parser = parse_date_range)]
+    range: Option<DateRange>,
+
+    /// Show the seven days from today on, one section per day
+    #[arg(
+        long,
+        conflicts_with_all = ["month", "day", "dates", "range"],
+    )]
+    week: bool,
+
+    /// Show a day bookmarked with `favorites add`, by its name
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = ["month", "day", "dates", "range", "week"],
+    )]
+    favorite: Option<String>,
+
+    /// Print the entries as JSON keyed by ISO date
+    #[arg(long, conflicts_with_all = ["feed", "first", "last"])]
+    json: bool,
+
+    /// Keep only these keys in each `--json` entry (comma-separated)
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "FIELDS",
+        requires = "json",
+    )]
+    fields: Vec<Field>,
+
+    /// Columns to show, in order (comma-separated; default `year,event`,
+    /// and holidays only have `event`, alias `text`)
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "COLUMNS")]
+    columns: Vec<Column>,
+
+    /// Wrap the table to N columns instead of the detected terminal width
+    /// (never narrower than 50, with at least 20 for the entry text)
+    #[arg(
+        short = 'w',
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u16).range(1..),
+    )]
+    width: Option<u16>,
+
+    /// Never wrap entry text wider than N columns, however wide the table
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u16).range(10..),
+    )]
+    max_text_width: Option<u16>,
+
+    /// Cut entry text that doesn't fit to one line ending in `…` instead
+    /// of wrapping it, so every entry keeps a single-line row
+    #[arg(long)]
+    truncate: bool,
+
+    /// Alignment of the entry text; years and ages are always
+    /// right-aligned
+    #[arg(
+        long,
+        value_enum,
+        value_name = "ALIGN",
+        default_value_t = Align::Left,
+    )]
+    align: Align,
+
+    /// Link entry text to its Wikipedia article (OSC 8 terminal
+    /// hyperlinks; ignored when stdout is not a terminal)
+    #[arg(long)]
+    links: bool,
+
+    /// Border style of the tables
+    #[arg(
+        long,
+        value_enum,
+        value_name = "PRESET",
+        default_value_t = TablePreset::Full,
+    )]
+    preset: TablePreset,
+
+    /// Colour theme for the tables and headers
+    #[arg(
+        long,
+        value_enum,
+        value_name = "THEME",
+        default_value_t = ThemeName::Default,
+    )]
+    theme: ThemeName,
+
+    /// Print an RSS or Atom feed instead of a table
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        conflicts_with_all = ["dates", "range", "week"],
+    )]
+    feed: Option<FeedFormat>,
+
+    /// Leave out sections with no entries instead of showing placeholders
+    #[arg(long)]
+    hide_empty: bool,
+
+    /// Collapse entries whose text repeats within a section, keeping the
+    /// first
+    #[arg(long)]
+    dedupe: bool,
+
+    /// Lead with the newest entry as a one-sentence headline
+    #[arg(
+        long,
+        conflicts_with_all = ["json", "feed", "first", "last"],
+    )]
+    headline: bool,
+
+    /// Instead of listing entries, chart how many fall in each century
+    #[arg(
+        long,
+        conflicts_with_all = ["json", "feed", "first", "last"],
+    )]
+    histogram: bool,
+
+    /// Instead of listing entries, chart how many events, births, deaths
+    /// and holidays the day has (fetches the combined `all` feed)
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "types", "json", "feed", "first", "last", "histogram",
+            "group_by_century",
+        ],
+    )]
+    count_by_type: bool,
+
+    /// Split dated entries into one table per century (“20th century”
+    /// for 1901–2000), each under its own sub-header
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "json", "feed", "plain", "histogram", "first", "last",
+        ],
+    )]
+    group_by_century: bool,
+
+    /// Print one line per entry, its fields joined by `--separator`,
+    /// instead of a table (with several sections, each line starts with
+    /// the section name)
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "json", "feed", "histogram", "first", "last",
+        ],
+    )]
+    plain: bool,
+
+    /// Field separator for `--plain`, joined in as is: nothing in the
+    /// data is quoted or escaped. Understands `\t`, `\n`, `\0` and `\\`
+    #[arg(
+        long,
+        value_name = "STRING",
+        value_parser = parse_separator,
+        default_value = "\\t",
+        requires = "plain",
+    )]
+    separator: String,
+
+    /// Print straight to the terminal even when the output is taller than
+    /// it, instead of through `$PAGER`
+    #[arg(long)]
+    no_pager: bool,
+
+    /// Also copy the output (as printed, minus colours) to the clipboard;
+    /// needs a build with the `clipboard` feature
+    #[arg(long)]
+    copy: bool,
+
+    /// Print the resolved language, type, days, URLs and cache files, then
+    /// stop without sending any request
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Add a column with each entry's full date and weekday (a dash
+    /// before the Gregorian calendar, 1583)
+    #[arg(long, conflicts_with_all = ["json", "feed"])]
+    context: bool,
+
+    /// Mark entries from a round number of years ago (25, 50, 75, 100, …)
+    #[arg(long, conflicts_with_all = ["json", "feed"])]
+    anniversaries: bool,
+
+    /// Lead each table row with its 1-based position, to refer to entries
+    /// by number
+    #[arg(long, conflicts_with_all = ["json", "feed", "plain"])]
+    numbered: bool,
+
+    /// Print only the earliest entry by year as a single line (holidays,
+    /// which have no year, the first alphabetically)
+    #[arg(long, conflicts_with_all = ["last", "dates", "range", "week"])]
+    first: bool,
+
+    /// Print only the latest entry by year as a single line (holidays the
+    /// last alphabetically)
+    #[arg(long, conflicts_with_all = ["dates", "range", "week"])]
+    last: bool,
+
+    /// Only entries from this year on (negative years are BC)
+    #[arg(long, value_name = "YEAR", allow_negative_numbers = true)]
+    year_from: Option<i32>,
+
+    /// Only entries up to and including this year
+    #[arg(long, value_name = "YEAR", allow_negative_numbers = true)]
+    year_to: Option<i32>,
+
+    /// Only entries from exactly this year, e.g. `--year 1969`
+    #[arg(
+        long,
+        value_name = "YEAR",
+        allow_negative_numbers = true,
+        conflicts_with_all = [
+            "year_from", "year_to", "last_century", "last_decade",
+        ],
+    )]
+    year: Option<i32>,
+
+    /// Only entries from the last 100 years (`--year-from` shortcut)
+    #[arg(long, conflicts_with_all = ["year_from", "last_decade"])]
+    last_century: bool,
+
+    /// Only entries from the last 10 years (`--year-from` shortcut)
+    #[arg(long, conflicts_with = "year_from")]
+    last_decade: bool,
+
+    /// Only entries whose text contains this (ignoring case unless
+    /// `--case-sensitive`)
+    #[arg(long, value_name = "TEXT")]
+    search: Option<String>,
+
+    /// Only holidays whose text contains this, e.g. `Feast` or `Day`;
+    /// like `--search`, but the dated sections are left alone
+    #[arg(long, value_name = "TEXT")]
+    holiday_filter: Option<String>,
+
+    /// Make `--search` match case exactly, e.g. to find “Turkey” the
+    /// country but not the bird (also applies to `--holiday-filter`).
+    /// Has no effect without one of those two
+    #[arg(long)]
+    case_sensitive: bool,
+
+    /// Drop entries whose text is shorter than this many characters, which
+    /// weeds out most of the feed's one-line filler
+    #[arg(long, value_name = "CHARS")]
+    min_length: Option<usize>,
+
+    /// Order of the rows (otherwise they keep Wikipedia's order)
+    #[arg(long, value_enum, value_name = "KEY")]
+    sort: Option<SortKey>,
+
+    /// Flip the final row order, after filtering and any `--sort`
+    #[arg(long)]
+    reverse: bool,
+
+    /// Show at most this many rows per section, counted from the top once
+    /// filtering, `--sort` and `--reverse` are done
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u16).range(1..),
+    )]
+    limit: Option<u16>,
+
+    /// Add an age-at-death column to `--type deaths` (blank when the entry
+    /// doesn't state a birth year)
+    #[arg(long)]
+    show_age: bool,
+
+    /// After each day, print the API URL it came from and when it was
+    /// fetched (for citations)
+    #[arg(long, conflicts_with_all = ["json", "feed"])]
+    show_source: bool,
+}
+
+impl HistoryArgs {
+    /// The `--type` values, without repeats; `--count-by-type` always
+    /// takes `all`.
+    fn picked_types(&self) -> Vec<EventType> {
+        if self.count_by_type {
+            return vec![EventType::All];
+        }
+        let mut picked = Vec::with_capacity(self.types.len());
+        for event_type in &self.types {
+            if !picked.contains(event_type) {
+                picked.push(*event_type);
+            }
+        }
+        picked
+    }
+
+    /// The type to fetch and validate against: the one picked, or `all`
+    /// (the combined feed) when there are several.
+    fn event_type(&self) -> EventType {
+        match self.picked_types().as_slice() {
+            [single] => *single,
+            _ => EventType::All,
+        }
+    }
+
+    /// The sections to show, in order: `all` stands for its parts, while
+    /// `anniversaries` stays one merged list.
+    fn sections(&self) -> Vec<EventType> {
+        let picked = self.picked_types();
+        if let [single] = picked.as_slice()
+            && *single != EventType::All
+        {
+            return picked;
+        }
+        let mut sections = Vec::new();
+        for part in picked.iter().flat_map(|picked| picked.parts()) {
+            if !sections.contains(part) {
+                sections.push(*part);
+            }
+        }
+        sections
+    }
+
+    /// `--columns`, or year and event when it wasn't given.
+    fn columns(&self) -> &[Column] {
+        if self.columns.is_empty() {
+            &[Column::Year, Column::Event]
+        } else {
+            &self.columns
+        }
+    }
+
+    /// Whether the output is the table view, which starts with a day header.
+    fn prints_day_header(&self) -> bool {
+        !self.silent
+            && !self.no_header
+            && !self.json
+            && self.feed.is_none()
+            && !self.first
+            && !self.last
+    }
+
+    /// The languages to fetch from in order: `--language`, then each
+    /// `--fallback` not already tried.
+    fn languages(&self) -> Vec<String> {
+        let mut langs = vec![self.language.clone()];
+        for lang in &self.fallback {
+            if !langs.contains(lang) {
+                langs.push(lang.clone());
+            }
+        }
+        langs
+    }
+
+    /// The header note for a day that the `lang` wiki served because
+    /// `--language` had nothing.
+    fn fallback_note(&self, lang: &str) -> Option<String> {
+        (lang != self.language).then(|| {
+            format!(
+                "(from {lang}.wikipedia.org; nothing found in '{}')",
+                self.language,
+            )
+        })
+    }
+
+    /// The flags given that `--type featured` has no use for: the picture
+    /// of the day is a text block, not a list of entries.
+    fn featured_conflicts(&self) -> Vec<&'static str> {
+        [
+            ("--feed", self.feed.is_some()),
+            ("--plain", self.plain),
+            ("--headline", self.headline),
+            ("--first", self.first),
+            ("--last", self.last),
+            ("--histogram", self.histogram),
+            ("--group-by-century", self.group_by_century),
+            ("--columns", !self.columns.is_empty()),
+            ("--fields", !self.fields.is_empty()),
+            ("--align", self.align != Align::Left),
+            ("--preset", self.preset != TablePreset::Full),
+            ("--links", self.links),
+            ("--numbered", self.numbered),
+            ("--context", self.context),
+            ("--anniversaries", self.anniversaries),
+            ("--show-age", self.show_age),
+            ("--show-source", self.show_source),
+            ("--hide-empty", self.hide_empty),
+            ("--dedupe", self.dedupe),
+            ("--sort", self.sort.is_some()),
+            ("--reverse", self.reverse),
+            ("--limit", self.limit.is_some()),
+            ("--search", self.search.is_some()),
+            ("--holiday-filter", self.holiday_filter.is_some()),
+            ("--min-length", self.min_length.is_some()),
+            ("--year", self.year.is_some()),
+            ("--year-from", self.year_from.is_some()),
+            ("--year-to", self.year_to.is_some()),
+            ("--last-century", self.last_century),
+            ("--last-decade", self.last_decade),
+            ("--copy", self.copy),
+        ]
+        .into_iter()
+        .filter_map(|(flag, given)| given.then_some(flag))
+        .collect()
+    }
+
+    /// Whether the “Finished in …” line belongs after the output; machine
+    /// formats, `--silent` and `--no-timing` leave it out.
+    fn prints_timing(&self) -> bool {
+        self.feed.is_none()
+            && !self.json
+            && !self.silent
+            && !self.no_timing
+            && !self.dry_run
+    }
+
+    /// Inclusive `(from, to)` year limits, resolving `--year`,
+    /// `--last-century` and `--last-decade` against `current_year`.
+    fn year_bounds(&self, current_year: i32) -> (Option<i32>, Option<i32>) {
+        if let Some(year) = self.year {
+            return (Some(year), Some(year));
+        }
+        let from = if self.last_century {
+            Some(current_year - 100)
+        } else if self.last_decade {
+            Some(current_year - 10)
+        } else {
+            self.year_from
+        };
+        (from, self.year_to)
+    }
+
+    /// Placeholder for a section with nothing to show, naming the year
+    /// when `--year` narrowed it down.
+    fn no_entries_text(&self, month: u32, day: u32) -> String {
+        match self.year {
+            Some(year) => format!(
+                "No events recorded for {month:02}-{day:02} in {year}."
+            ),
+            None => "No entries of this type found for this day.".to_string(),
+        }
+    }
+
+    /// Entry text fitted to `width` columns: wrapped, or cut short with
+    /// `--truncate`.
+    fn fit_text(&self, text: &str, width: usize) -> String {
+        if self.truncate {
+            truncate_to_width(text, width)
+        } else {
+            fill(text, width)
+        }
+    }
+
+    fn cache_policy(&self) -> CachePolicy {
+        CachePolicy {
+            offline: self.offline,
+            refresh: self.refresh,
+            stale_ok: self.stale_ok,
+            ttl_secs: self.cache_ttl,
+        }
+    }
+}
+
+/* --------------------------------------------------------------------------
+ *                                 main
+ * ---------------------------------------------------------------------- */
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let verbose = cli.verbose;
+
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            // `{:?}` adds the “Caused by:” chain behind the headline
+            if verbose {
+                eprintln!("Error: {err:?}");
+            } else {
+                eprintln!("Error: {err}");
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(mut cli: Cli) -> Result<()> {
+    // Checked here: clap can't tie a global flag to a top-level one
+    if cli.statistics && cli.date_fmt.is_some() {
+        bail!("--date-fmt prints the time, so it can't go with --statistics");
+    }
+    let now = now_in(cli.zone(), clock()?);
+    // https://no-color.org: any non-empty value counts, even `0`
+    let no_color_env =
+        std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    cli.no_color |= no_color_env;
+    let color = !cli.no_color;
+    if !color {
+        cli.bar_color = BarColor::None;
+        match &mut cli.command {
+            Some(Command::History(args)) => args.theme = ThemeName::Mono,
+            Some(Command::Today(args)) => args.theme = ThemeName::Mono,
+            _ => {}
+        }
+    }
+
+    match &cli.command {
+        Some(Command::Now(args)) if args.json => {
+            println!("{}", now_json(now)?);
+        }
+        Some(Command::Now(args)) => {
+            let format = cli.clock_format(args.format.as_ref());
+            show_current_time(now, format.as_ref(), color);
+        }
+        Some(Command::History(args)) => {
+            let start = Instant::now();
+            let wiki = Wiki::new(&cli, args.cache_policy())?;
+            show_on_this_day(args, now, &wiki).await?;
+            if args.prints_timing() {
+                println!("\nFinished in {:.2?}.", start.elapsed());
+            }
+        }
+        Some(Command::Today(args)) => {
+            show_current_time(now, cli.clock_format(None).as_ref(), color);
+            show_time_statistics(now, &cli)?;
+            println!();
+            let wiki = Wiki::new(&cli, CachePolicy::default())?;
+            show_today_summary(args, now, &wiki).await?;
+        }
+        Some(Command::Diff(args)) => {
+            let wiki = Wiki::new(&cli, CachePolicy::default())?;
+            show_day_diff(args, color, &wiki).await?;
+        }
+        Some(Command::Add(args)) => {
+            show_shifted(args, cli.date_fmt.as_deref(), false)?;
+        }
+        Some(Command::Sub(args)) => {
+            show_shifted(args, cli.date_fmt.as_deref(), true)?;
+        }
+        Some(Command::Ago(args)) => {
+            let seconds = (args.timestamp.at - now).num_seconds();
+            println!("{}", relative_time(seconds));
+        }
+        Some(Command::Countdown(args)) => {
+            let days = days_until(now.date_naive(), args.date, args.leap_day);
+            let plural = if days == 1 { "" } else { "s" };
+            println!("{days} day{plural} until {}", args.date.format("%m-%d"));
+        }
+        Some(Command::Favorites(args)) => {
+            show_favorites(&args.action).await?;
+        }
+        Some(Command::Completions(args)) => {
+            let mut cmd = Cli::command();
+            let bin_name = cmd.get_name().to_string();
+            clap_complete::generate(
+                args.shell,
+                &mut cmd,
+                bin_name,
+                &mut std::io::stdout(),
+            );
+        }
+        Some(Command::Man(args)) => {
+            write_man_pages(args.out_dir.as_deref())?;
+        }
+        Some(Command::Schema(args)) => {
+            println!("{}", json_schema(args.output)?);
+        }
+        None if cli.watch => watch(&cli).await?,
+        None => {
+            if cli.statistics {
+                show_time_statistics(now, &cli)?;
+            } else {
+                let format = cli.clock_format(cli.format.as_ref());
+                show_current_time(now, format.as_ref(), color);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The current moment, unless `TIME_CLI_NOW` (unix seconds, YYYY-MM-DD or
+/// RFC 3339) pins it for reproducible output.
+fn clock() -> Result<DateTime<Utc>> {
+    match std::env::var("TIME_CLI_NOW") {
+        Ok(pinned) => parse_instant(&pinned)
+            .map(|moment| moment.at.to_utc())
+            .map_err(|err| anyhow!("TIME_CLI_NOW: {err}")),
+        Err(_) => Ok(Utc::now()),
+    }
+}
+
+/// `now` as seen in `zone`, or in the local zone when that's `None`.
+fn now_in(zone: Option<Tz>, now: DateTime<Utc>) -> DateTime<FixedOffset> {
+    match zone {
+        Some(tz) => now.with_timezone(&tz).fixed_offset(),
+        None => now.with_timezone(&Local).fixed_offset(),
+    }
+}
+
+/* --------------------------------------------------------------------------
+ *                              man pages
+ * ---------------------------------------------------------------------- */
+
+/// Render a man page for the top-level command and every visible
+/// subcommand, either as `<name>.1` files in `out_dir` or to stdout.
+fn write_man_pages(out_dir: Option<&Path>) -> Result<()> {
+    let mut cmd = Cli::command();
+    cmd.build(); // propagate version & global args to subcommands
+
+    let name = cmd.get_name().to_string();
+    let mut pages = vec![(name.clone(), cmd.clone())];
+    for sub in cmd
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set() && sub.get_name() != "help")
+    {
+        let page_name = format!("{name}-{}", sub.get_name());
+        pages.push((page_name.clone(), sub.clone().name(page_name)));
+    }
+
+    if let Some(dir) = out_dir {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    for (page_name, page) in pages {
+        let mut buffer = Vec::new();
+        clap_mangen::Man::new(page).render(&mut buffer)?;
+
+        match out_dir {
+            Some(dir) => {
+                std::fs::write(dir.join(format!("{page_name}.1")), buffer)?
+            }
+            None => stdout.write_all(&buffer)?,
+        }
+    }
+
+    Ok(())
+}
+
+/* --------------------------------------------------------------------------
+ *                              Wikipedia
+ * ---------------------------------------------------------------------- */
+
+/// The Wikipedia client the global flags ask for, and which of its
+/// diagnostics go to stderr.
+struct Wiki {
+    client: WikiClient,
+    /// `--show-url`
+    show_url: bool,
+    /// `--show-cache-status`
+    show_cache_status: bool,
+    /// `--verbose`
+    verbose: bool,
+}
+
+impl Wiki {
+    fn new(cli: &Cli, policy: CachePolicy) -> Result<Self> {
+        let client = WikiClient::new(FetchOptions {
+            user_agent: cli.user_agent.clone(),
+            // Lets the tests point every request at a local mock
+            base_url: std::env::var("TEST_WIKIPEDIA_API_URL").ok(),
+            policy,
+            // Before sending, so a request that fails still shows its URL
+            on_request: cli.show_url.then_some(|url| eprintln!("GET {url}")),
+        })?;
+        Ok(Self {
+            client,
+            show_url: cli.show_url,
+            show_cache_status: cli.show_cache_status,
+            verbose: cli.verbose,
+        })
+    }
+
+    /// Whether fetching prints anything to stderr.
+    fn is_chatty(&self) -> bool {
+        self.show_url || self.show_cache_status || self.verbose
+    }
+
+    /// `--verbose`'s line for a request to `url`, if one went out
+    /// (`--show-url`'s comes from the client as the request is sent).
+    fn report_request(&self, url: &str, metrics: &FetchMetrics) {
+        if let Some(status) = metrics.status
+            && self.verbose
+        {
+            let (bytes, ms) = (metrics.bytes, metrics.round_trip.as_millis());
+            eprintln!("{status} {url}: {bytes} bytes in {ms} ms");
+        }
+    }
+
+    /// The request, cache and stale-copy lines for a fetch of `url`.
+    fn report_fetch(
+        &self,
+        url: &str,
+        metrics: &FetchMetrics,
+        fetched_at: i64,
+        refresh_error: Option<&str>,
+    ) {
+        self.report_request(url, metrics);
+        if self.show_cache_status {
+            let hit = if metrics.cache_hit { "hit" } else { "miss" };
+            eprintln!("cache: {hit} {url}");
+        }
+        if let Some(err) = refresh_error {
+            let fetched_at = DateTime::from_timestamp(fetched_at, 0)
+                .map(|at| at.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+                .unwrap_or_else(|| "an unknown time".to_string());
+            eprintln!("Warning: {err}; showing cached data from {fetched_at}");
+        }
+    }
+
+    /// Everything worth saying on stderr about how `fetched` was got.
+    fn report(&self, fetched: &Fetched) {
+        let url = &fetched.url;
+        let refresh_error = fetched.refresh_error.as_deref();
+        let fetched_at = fetched.fetched_at;
+        self.report_fetch(url, &fetched.metrics, fetched_at, refresh_error);
+        if self.verbose && fetched.response.is_blank() {
+            eprintln!(
+                "{url}: the feed itself is empty (no entries of any type)",
+            );
+        }
+    }
+
+    /// [`Wiki::report`] for a picture of the day.
+    fn report_image(&self, fetched: &FetchedImage) {
+        let refresh_error = fetched.refresh_error.as_deref();
+        self.report_fetch(
+            &fetched.url,
+            &fetched.metrics,
+            fetched.fetched_at,
+            refresh_error,
+        );
+    }
+}
+
+/// Fetch and print the requested days; today's date in `now`'s zone is
+/// what `--month`/`--day` default to.
+async fn show_on_this_day(
+    args: &HistoryArgs,
+    now: DateTime<FixedOffset>,
+    wiki: &Wiki,
+) -> Result<()> {
+    let start = Instant::now();
+    let today = now.date_naive();
+    let sections = args.sections();
+    // Whether any section's entries carry a year
+    let dated = sections.iter().any(|section| *section != EventType::Holidays);
+    if args.show_age && !sections.contains(&EventType::Deaths) {
+        bail!("--show-age only applies to --type deaths");
+    }
+    if args.event_type() == EventType::Holidays
+        && args.columns.contains(&Column::Year)
+    {
+        bail!("holidays have no year; the only --columns value is `text`");
+    }
+    if args.picked_types().len() > 1
+        && args.types.iter().any(|event_type| {
+            matches!(event_type, EventType::Anniversaries | EventType::Featured)
+        })
+    {
+        bail!("--type anniversaries and featured can't be combined");
+    }
+    if args.copy && !cfg!(feature = "clipboard") {
+        bail!("--copy needs time_cli built with `--features clipboard`");
+    }
+    match args.sort {
+        Some(SortKey::Year) if !dated => {
+            bail!("holidays have no year; sort them with --sort text");
+        }
+        Some(SortKey::Text) if !sections.contains(&EventType::Holidays) => {
+            bail!("--sort text only applies to --type holidays");
+        }
+        _ => {}
+    }
+    if args.holiday_filter.is_some()
+        && !sections.contains(&EventType::Holidays)
+    {
+        bail!("--holiday-filter only applies to --type holidays (or all)");
+    }
+    if args.group_by_century
+        && (!dated || sections.contains(&EventType::Anniversaries))
+    {
+        bail!("holidays have no year, so --group-by-century can't use them");
+    }
+    if args.histogram
+        && !matches!(
+            args.event_type(),
+            EventType::Events | EventType::Births | EventType::Deaths
+        )
+    {
+        bail!("--histogram needs --type events, births or deaths");
+    }
+    if args.event_type().parts().len() > 1
+        && (args.feed.is_some() || args.first || args.last)
+    {
+        bail!("--feed, --first and --last need a single --type");
+    }
+    // Looked up first: reading the favorites may wait on their lock
+    let favorite = match &args.favorite {
+        Some(name) => Some(favorite_day(name).await?),
+        None => None,
+    };
+    let days = requested_days(args, today, favorite)?;
+    if args.event_type() == EventType::Featured {
+        let conflicts = args.featured_conflicts();
+        if !conflicts.is_empty() {
+            bail!(
+                "--type featured can't be combined with {}",
+                conflicts.join(", "),
+            );
+        }
+        if args.dry_run {
+            return print_dry_run(args, &days, &wiki.client);
+        }
+        return show_featured(args, &days, wiki).await;
+    }
+
+    let event_type_name = args.event_type().api_segment();
+    if args.dry_run {
+        return print_dry_run(args, &days, &wiki.client);
+    }
+
+    // A single day's header goes out before the fetch, so the spinner
+    // below it has context and the terminal isn't blank while we wait
+    let early_header = days.len() == 1 && args.prints_day_header();
+    let single_day_header = || {
+        let (month, day) = (days[0].month(), days[0].day());
+        day_header(args.theme, &args.language, month, day, None)
+    };
+    if early_header {
+        print!("{}", single_day_header());
+        std::io::stdout().flush()?;
+    }
+
+    let spinner = history_progress(args, wiki, &days, event_type_name)?;
+
+    // Fetch & parse JSON, a few days at a time
+    let total = days.len();
+    let langs = args.languages();
+    let on_done = |done: usize| {
+        if let Some(pb) = &spinner {
+            pb.set_position(done as u64);
+        }
+    };
+    let fetch =
+        wiki.client.fetch_days(&langs, args.event_type(), &days, on_done);
+    let mut results = unless_interrupted(fetch, spinner.as_ref()).await;
+
+    if let Some(pb) = spinner {
+        pb.finish_and_clear();
+    }
+    for fetched in results.iter().flatten() {
+        wiki.report(fetched);
+    }
+
+    // A single day fails outright; a batch reports bad days and goes on
+    if let [Err(_)] = results.as_slice() {
+        return Err(results.remove(0).unwrap_err());
+    }
+    let (year_from, year_to) = args.year_bounds(today.year());
+    let mut fetched = Vec::with_capacity(total);
+    let mut metrics = RunMetrics::default();
+    for (date, result) in days.iter().zip(results) {
+        match result {
+            Ok(mut day) => {
+                metrics.add(&day);
+                // Before filtering: an empty feed, not an empty result
+                let blank = day.response.is_blank();
+                day.response.retain_years(year_from, year_to);
+                if let Some(needle) = &args.search {
+                    day.response.retain_matching(needle, args.case_sensitive);
+                }
+                if let Some(needle) = &args.holiday_filter {
+                    day.response
+                        .retain_holidays_matching(needle, args.case_sensitive);
+                }
+                if let Some(min_chars) = args.min_length {
+                    day.response.retain_min_length(min_chars);
+                }
+                let removed = if args.dedupe {
+                    day.response.dedupe()
+                } else {
+                    0
+                };
+                match args.sort {
+                    Some(SortKey::Year) => day.response.sort_by_year(),
+                    Some(SortKey::Text) => {
+                        day.response.holidays.sort_by_cached_key(|holiday| {
+                            holiday.text.to_lowercase()
+                        });
+                    }
+                    None => {}
+                }
+                if args.reverse {
+                    day.response.reverse();
+                }
+                if let Some(limit) = args.limit {
+                    day.response.truncate_shown(usize::from(limit));
+                }
+                fetched.push((*date, day, removed, blank));
+            }
+            Err(err) => eprintln!("Error: could not fetch {date}: {err}"),
+        }
+    }
+
+    let mut out = String::new();
+    if args.json {
+        let sections = args.sections();
+        let mut by_date = serde_json::Map::new();
+        for (date, day, _, _) in &fetched {
+            let mut entries =
+                serde_json::to_value(entries_json(&sections, &day.response))?;
+            if !args.fields.is_empty() {
+                project_fields(&mut entries, &args.fields);
+            }
+            by_date.insert(date.to_string(), entries);
+        }
+        writeln!(out, "{}", serde_json::to_string_pretty(&by_date)?)?;
+    } else {
+        for (i, (date, day, removed, blank)) in fetched.iter().enumerate() {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            render_day(
+                &mut out,
+                args,
+                (date.month(), date.day()),
+                day,
+                early_header,
+                now,
+            )?;
+            if *removed > 0 && args.feed.is_none() && !args.silent {
+                let noun = if *removed == 1 { "entry" } else { "entries" };
+                writeln!(out, "Removed {removed} duplicate {noun}.")?;
+            }
+            if *blank && args.feed.is_none() && !args.silent {
+                writeln!(
+                    out,
+                    "{}.wikipedia.org sent an empty feed for this day; \
+                     another --language (or --fallback) may have entries.",
+                    day.lang,
+                )?;
+            }
+            if args.show_source {
+                writeln!(out, "{}", source_footer(day))?;
+            }
+        }
+    }
+
+    let header = early_header.then(single_day_header);
+    if args.copy {
+        copy_to_clipboard(&clipboard_text(header.as_deref(), &out))?;
+        eprintln!("Copied to clipboard");
+    }
+
+    // Too tall for the terminal: hand it to a pager. The early header is
+    // already on screen, so it counts towards the height but isn't paged
+    if !args.no_pager
+        && std::io::stdout().is_terminal()
+        && terminal_size::terminal_size().is_some_and(|(_, height)| {
+            too_tall(header.as_deref(), &out, height.0)
+        })
+    {
+        page(&out)?;
+    } else {
+        print!("{out}");
+        std::io::stdout().flush()?;
+    }
+
+    if wiki.verbose {
+        eprintln!("{}", metrics.summary(start.elapsed()));
+    }
+    let failed = total - fetched.len();
+    if failed > 0 {
+        bail!("{failed} of {total} days could not be fetched");
+    }
+    Ok(())
+}
+
+/// `--dry-run`: what a real run would fetch and where it would cache it.
+fn print_dry_run(
+    args: &HistoryArgs,
+    days: &[NaiveDate],
+    client: &WikiClient,
+) -> Result<()> {
+    let event_type = args.event_type();
+    let segment = event_type.api_segment();
+    let mut langs = vec![args.language.as_str()];
+    langs.extend(args.fallback.iter().map(String::as_str));
+    let picked: Vec<String> =
+        args.picked_types().iter().map(ToString::to_string).collect();
+    println!("Language : {}", langs.join(", then "));
+    println!("Type     : {} (feed: {segment})", picked.join(", "));
+    for date in days {
+        let (month, day) = (date.month(), date.day());
+        let location = match event_type {
+            EventType::Featured => {
+                client.featured_location(&args.language, *date)?
+            }
+            _ => client.feed_location(&args.language, event_type, month, day)?,
+        };
+        let cache = location.cache_path.map_or_else(
+            || "(no cache directory)".to_string(),
+            |path| path.display().to_string(),
+        );
+        println!("{}    : GET {}", date.format("%m-%d"), location.url);
+        println!("           cache: {cache}");
+    }
+    Ok(())
+}
+
+/// The fetch indicator for `days` of the `what` feed, unless `--quiet` or
+/// `--silent` asks for none; `--show-url` / `--show-cache-status` /
+/// `--verbose` lines would tear through it, so they rule it out too.
+fn history_progress(
+    args: &HistoryArgs,
+    wiki: &Wiki,
+    days: &[NaiveDate],
+    what: &str,
+) -> Result<Option<ProgressBar>> {
+    if args.quiet || args.silent || wiki.is_chatty() {
+        return Ok(None);
+    }
+    let when = match days {
+        [date] => date.format("%m-%d").to_string(),
+        _ => format!("{} days", days.len()),
+    };
+    let lang = &args.language;
+    let message = format!("Fetching {what} for {when} ({lang})");
+    fetch_progress(days.len(), message).map(Some)
+}
+
+/// Wait for `fetch`, unless Ctrl-C comes first: then tidy up the
+/// `spinner` and exit the way a shell would.
+async fn unless_interrupted<T>(
+    fetch: impl std::future::Future<Output = T>,
+    spinner: Option<&ProgressBar>,
+) -> T {
+    tokio::select! {
+        results = fetch => results,
+        _ = tokio::signal::ctrl_c() => {
+            clean_up_after_interrupt(spinner);
+            std::process::exit(130); // 128 + SIGINT
+        }
+    }
+}
+
+/// The fetch indicator: a spinner while one day loads, or a bar counting
+/// finished days, with an ETA, for several.
+fn fetch_progress(days: usize, message: String) -> Result<ProgressBar> {
+    const TICKS: [&str; 10] =
+        ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+    let pb = if days > 1 {
+        let pb = ProgressBar::new(days as u64);
+        pb.set_style(
+            ProgressStyle::with_template(
+                "{spinner:.blue} {msg} [{bar:24.blue}] {pos}/{len} (ETA {eta})",
+            )?
+            .tick_strings(&TICKS)
+            .progress_chars("=> "),
+        );
+        pb
+    } else {
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(
+            ProgressStyle::with_template("{spinner:.blue} {msg}")?
+                .tick_strings(&TICKS),
+        );
+        pb
+    };
+    pb.enable_steady_tick(Duration::from_millis(120));
+    pb.set_message(message);
+    Ok(pb)
+}
+
+/// Stop `spinner` and bring the cursor back after Ctrl-C, so the terminal
+/// isn't left with a hidden cursor or a half-drawn spinner line.
+fn clean_up_after_interrupt(spinner: Option<&ProgressBar>) {
+    if let Some(pb) = spinner {
+        pb.finish_and_clear();
+    }
+    let mut stderr = std::io::stderr();
+    if stderr.is_terminal() {
+        let _ = write!(stderr, "\x1b[?25h"); // DECTCEM: show cursor
+        let _ = stderr.flush();
+    }
+}
+
+/// The calendar days to show: today and the six after it for `--week`,
+/// the `--date`/`--range` values in the order given without repeats, or
+/// else the single `favorite` (`--favorite`, already looked up) or
+/// `--month`/`--day` (defaulting to today) in the most recent year that
+/// has it.
+fn requested_days(
+    args: &HistoryArgs,
+    today: NaiveDate,
+    favorite: Option<(u32, u32)>,
+) -> Result<Vec<NaiveDate>> {
+    if args.week {
+        return Ok(today.iter_days().take(7).collect());
+    }
+    if args.dates.is_empty() && args.range.is_none() {
+        let (month, day) = favorite.unwrap_or((
+            args.month.unwrap_or(today.month()),
+            args.day.unwrap_or(today.day()),
+        ));
+
+        // Feb-29 may need to go back to the last leap year
+        let Some(date) = (0..8).find_map(|back| {
+            NaiveDate::from_ymd_opt(today.year() - back, month, day)
+        }) else {
+            bail!("'{month:02}-{day:02}' is not a valid calendar date");
+        };
+        return Ok(vec![date]);
+    }
+
+    let mut days = Vec::new();
+    let range_days = args.range.iter().flat_map(|range| range.days());
+    for date in args.dates.iter().copied().chain(range_days) {
+        if !days.contains(&date) {
+            days.push(date);
+        }
+    }
+    Ok(days)
+}
+
+/// One day of `history --json`: the selected entries in display order,
+/// or for `all` an object with one such array per category.
+#[derive(Serialize, JsonSchema)]
+#[serde(untagged)]
+enum DayJson<'a> {
+    Entries(Vec<JsonEntry<'a>>),
+    Sections(BTreeMap<String, Vec<JsonEntry<'a>>>),
+}
+
+fn entries_json<'a>(
+    sections: &[EventType],
+    response: &'a OnThisDayResponse,
+) -> DayJson<'a> {
+    let entries = |section: EventType| match section {
+        EventType::Holidays => {
+            response.holidays.iter().map(JsonEntry::Holiday).collect()
+        }
+        _ => {
+            let events = response.events_of(section).unwrap_or_default();
+            events.iter().rev().map(JsonEntry::Event).collect()
+        }
+    };
+    let parts: Vec<EventType> =
+        sections.iter().flat_map(|section| section.parts()).copied().collect();
+    match parts.as_slice() {
+        [single] => DayJson::Entries(entries(*single)),
+        _ => DayJson::Sections(
+            parts
+                .iter()
+                .map(|part| (part.to_string(), entries(*part)))
+                .collect(),
+        ),
+    }
+}
+
+/// The two shapes of a `--json` entry: `{"year", "text"[, "url"]}` for
+/// events, births and deaths, `{"text"}` for holidays. An array only
+/// ever holds one of them.
+#[derive(Serialize, JsonSchema)]
+#[serde(untagged)]
+enum JsonEntry<'a> {
+    Event(&'a Event),
+    Holiday(&'a Holiday),
+}
+
+/// Totals over a run's fetches for `--verbose`'s closing summary.
+#[derive(Default)]
+struct RunMetrics {
+    days: usize,
+    cache_hits: usize,
+    bytes: usize,
+    parse_time: Duration,
+    /// Entries per [`RunMetrics::COUNTED`] category as fetched, before any
+    /// filtering
+    entries: [usize; 5],
+}
+
+impl RunMetrics {
+    const COUNTED: [EventType; 5] = [
+        EventType::Events,
+        EventType::Births,
+        EventType::Deaths,
+        EventType::Holidays,
+        EventType::Selected,
+    ];
+
+    fn add(&mut self, fetched: &Fetched) {
+        self.days += 1;
+        self.cache_hits += usize::from(fetched.metrics.cache_hit);
+        self.bytes += fetched.metrics.bytes;
+        self.parse_time += fetched.metrics.parse_time;
+
+        let response = &fetched.response;
+        for (total, part) in self.entries.iter_mut().zip(Self::COUNTED) {
+            *total += response
+                .events_of(part)
+                .map_or(response.holidays.len(), <[Event]>::len);
+        }
+    }
+
+    /// Two lines: where the time and bytes went, then the entry counts.
+    fn summary(&self, elapsed: Duration) -> String {
+        let entries: Vec<String> = Self::COUNTED
+            .iter()
+            .zip(self.entries)
+            .map(|(kind, count)| {
+                format!("{} {count}", kind.title().to_lowercase())
+            })
+            .collect();
+        format!(
+            "Summary: days {days}, cache hits {hits}, misses {misses}, \
+             bytes received {bytes}, parsing {parse:.2?}, total {elapsed:.2?}\n\
+             Entries: {entries}",
+            days = self.days,
+            hits = self.cache_hits,
+            misses = self.days - self.cache_hits,
+            bytes = self.bytes,
+            parse = self.parse_time,
+            entries = entries.join(", "),
+        )
+    }
+}
+
+/// “Source: <url> (fetched …)” line for citing where a day came from.
+fn source_footer(day: &Fetched) -> String {
+    let fetched_at = DateTime::from_timestamp(day.fetched_at, 0)
+        .map(|at| at.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    format!("Source: {} (fetched {fetched_at})", day.url)
+}
+
+/// Drop every key not in `fields` from the entry objects in `entries` (an
+/// array of entries, or an object of such arrays).
+fn project_fields(entries: &mut serde_json::Value, fields: &[Field]) {
+    match entries {
+        serde_json::Value::Array(items) => {
+            for item in items {
+                if let serde_json::Value::Object(entry) = item {
+                    entry.retain(|key, _| {
+                        fields.iter().any(|field| field.key() == key)
+                    });
+                }
+            }
+        }
+        serde_json::Value::Object(sections) => {
+            for section in sections.values_mut() {
+                project_fields(section, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Render one day's entries in the requested presentation into `out`.
+fn render_day(
+    out: &mut String,
+    args: &HistoryArgs,
+    (month, day): (u32, u32),
+    fetched: &Fetched,
+    header_printed: bool,
+    now: DateTime<FixedOffset>,
+) -> std::fmt::Result {
+    let current_year = now.year();
+    let response = &fetched.response;
+    if let Some(format) = args.feed {
+        let feed = render_feed(format, args, fetched, (month, day), now);
+        return writeln!(out, "{feed}");
+    }
+
+    if args.first || args.last {
+        return match pick_extreme(args.event_type(), response, args.last) {
+            Some(line) => writeln!(out, "{line}"),
+            None => writeln!(out, "{}", args.no_entries_text(month, day)),
+        };
+    }
+
+    let width = table_width(args.width);
+    let theme = args.theme.theme();
+    let sections = args.sections();
+
+    // Say so when a fallback language stood in for the requested one
+    let source = args.fallback_note(&fetched.lang);
+    if !args.silent {
+        match (header_printed || args.no_header, source) {
+            (false, source) => out.push_str(&day_header(
+                args.theme,
+                &args.language,
+                month,
+                day,
+                source.as_deref(),
+            )),
+            (true, Some(source)) => writeln!(out, "{source}\n")?,
+            (true, None) => {}
+        }
+    }
+    if args.histogram {
+        let events = response.events_of(args.event_type()).unwrap_or_default();
+        return writeln!(out, "{}", century_histogram(events, &theme));
+    }
+    if args.count_by_type {
+        return writeln!(out, "{}", type_counts_chart(response, &theme));
+    }
+    if args.headline
+        && let Some(line) = headline(args.event_type(), response)
+    {
+        if theme.decorate {
+            writeln!(out, "{}\n", line.bold())?;
+        } else {
+            writeln!(out, "{line}\n")?;
+        }
+    }
+    let titled = sections.len() > 1;
+    let sections: Vec<EventType> = sections
+        .iter()
+        .copied()
+        .filter(|section| !args.hide_empty || !response.is_empty_for(*section))
+        .collect();
+    if sections.is_empty() {
+        writeln!(out, "Nothing recorded for this day.")?;
+    }
+    for (i, section) in sections.iter().enumerate() {
+        if args.plain {
+            let lines =
+                plain_lines(args, *section, response, titled, current_year);
+            for line in lines {
+                writeln!(out, "{line}")?;
+            }
+            continue;
+        }
+        if titled && !args.silent {
+            let gap = if i > 0 { "\n" } else { "" };
+            let title = section.title();
+            if theme.decorate {
+                writeln!(out, "{gap}{}", title.bold())?;
+            } else {
+                writeln!(out, "{gap}{title}")?;
+            }
+        }
+        if args.group_by_century
+            && let Some(events) = response.events_of(*section)
+            && !events.is_empty()
+        {
+            for (label, events) in century_groups(events, args.reverse) {
+                if theme.decorate {
+                    writeln!(out, "{}", label.bold())?;
+                } else {
+                    writeln!(out, "{label}")?;
+                }
+                let mut group = OnThisDayResponse::default();
+                *group.events_of_mut(*section).unwrap() = events;
+                let table = build_table(
+                    args,
+                    *section,
+                    &group,
+                    width,
+                    (month, day),
+                    current_year,
+                );
+                writeln!(out, "{table}")?;
+            }
+            continue;
+        }
+        let table = build_table(
+            args,
+            *section,
+            response,
+            width,
+            (month, day),
+            current_year,
+        );
+        writeln!(out, "{table}")?;
+    }
+    Ok(())
+}
+
+/// `--type featured`: each day's picture of the day as a short text block,
+/// or all of them as JSON keyed by ISO date with `--json`. Days that fail
+/// are reported and skipped, as in [`show_on_this_day`].
+async fn show_featured(
+    args: &HistoryArgs,
+    days: &[NaiveDate],
+    wiki: &Wiki,
+) -> Result<()> {
+    let spinner = history_progress(args, wiki, days, "featured")?;
+    let on_done = |done: usize| {
+        if let Some(pb) = &spinner {
+            pb.set_position(done as u64);
+        }
+    };
+    let langs = args.languages();
+    let fetch = wiki.client.fetch_featured_days(&langs, days, on_done);
+    let mut results = unless_interrupted(fetch, spinner.as_ref()).await;
+    if let Some(pb) = spinner {
+        pb.finish_and_clear();
+    }
+    for fetched in results.iter().flatten() {
+        wiki.report_image(fetched);
+    }
+    // A single day fails outright; a batch reports bad days and goes on
+    if let [Err(_)] = results.as_slice() {
+        return Err(results.remove(0).unwrap_err());
+    }
+
+    let theme = args.theme.theme();
+    let width =
+        text_wrap_width(table_width(args.width), 0, args.max_text_width);
+    let mut by_date = BTreeMap::new();
+    let mut shown = 0;
+    let mut failed = 0;
+    for (date, result) in days.iter().zip(results) {
+        let fetched = match result {
+            Ok(fetched) => fetched,
+            Err(err) => {
+                eprintln!("Error: could not fetch {date}: {err}");
+                failed += 1;
+                continue;
+            }
+        };
+        if args.json {
+            by_date.insert(date.to_string(), fetched.image);
+            continue;
+        }
+        if shown > 0 {
+            println!();
+        }
+        shown += 1;
+        if args.prints_day_header() {
+            let (month, day) = (date.month(), date.day());
+            let note = args.fallback_note(&fetched.lang);
+            let header = day_header(
+                args.theme,
+                &args.language,
+                month,
+                day,
+                note.as_deref(),
+            );
+            print!("{header}");
+        }
+        let Some(image) = fetched.image else {
+            println!("No featured picture for this day.");
+            continue;
+        };
+        let label = "Picture of the day:";
+        let title = image.title.strip_prefix("File:").unwrap_or(&image.title);
+        if theme.decorate {
+            println!("{} {title}", label.bold());
+        } else {
+            println!("{label} {title}");
+        }
+        if let Some(description) = &image.description {
+            println!("{}", args.fit_text(description, width));
+        }
+        println!("Source: {}", image.file_page);
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&by_date)?);
+    }
+    if failed > 0 {
+        bail!("{failed} of {} days could not be fetched", days.len());
+    }
+    Ok(())
+}
+
+/// Whether `out`, below the `header` already printed ahead of it, fills
+/// a terminal `height` rows tall.
+fn too_tall(header: Option<&str>, out: &str, height: u16) -> bool {
+    let header_rows = header.map_or(0, |header| header.lines().count());
+    header_rows + out.lines().count() >= usize::from(height)
+}
+
+/// Show `text` through `$PAGER` (default `less -R`, which keeps colours),
+/// the way git does for long output. Prints it directly if the pager
+/// won't start.
+fn page(text: &str) -> Result<()> {
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| "less -R".to_string());
+    let mut words = pager.split_whitespace();
+    let program = words.next().unwrap_or("less");
+    let child = std::process::Command::new(program)
+        .args(words)
+        .stdin(std::process::Stdio::piped())
+        .spawn();
+    match child {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                // Quitting the pager early closes the pipe; that's fine
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            child.wait()?;
+        }
+        Err(_) => print!("{text}"),
+    }
+    Ok(())
+}
+
+/// What `--copy` puts on the clipboard: the day's output behind the
+/// `header` printed ahead of it, with colours and links stripped, since
+/// chat boxes would show the escape codes.
+fn clipboard_text(header: Option<&str>, out: &str) -> String {
+    let mut text = String::new();
+    let mut chars = header.unwrap_or_default().chars().chain(out.chars());
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI (colours): parameters up to a final byte in @..=~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC (hyperlinks): up to BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    let st = c == '\x1b' && chars.next() == Some('\\');
+                    if c == '\x07' || st {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(text: &str) -> Result<()> {
+    let mut clipboard = arboard::Clipboard::new()
+        .map_err(|err| anyhow!("Could not open the clipboard: {err}"))?;
+    clipboard.set_text(text)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn copy_to_clipboard(_text: &str) -> Result<()> {
+    bail!("--copy needs time_cli built with `--features clipboard`")
+}
+
+/// The human-readable “— On This Day: July 20” header plus a blank line,
+/// with an optional note after the date.
+fn day_header(
+    theme: ThemeName,
+    lang: &str,
+    month: u32,
+    day: u32,
+    note: Option<&str>,
+) -> String {
+    let fake_year = 2024; // leap year → Feb-29 always valid
+    let header_date = NaiveDate::from_ymd_opt(fake_year, month, day).unwrap();
+    let label = "— On This Day:";
+    let label = if theme.theme().decorate {
+        label.bold().underline().to_string()
+    } else {
+        label.to_string()
+    };
+    let note = note.map(|note| format!(" {note}")).unwrap_or_default();
+    format!("{label} {}{note}\n\n", header_day(header_date, lang))
+}
+
+/// The day in the header written the way `lang` readers expect: “July 4”
+/// in English, “4. Juli” in German, “4 juillet” in French and so on.
+/// Languages without a pattern here fall back to English.
+fn header_day(date: NaiveDate, lang: &str) -> String {
+    use chrono::Locale;
+
+    let (pattern, locale) = match lang {
+        "de" => ("%-d. %B", Locale::de_DE),
+        "da" => ("%-d. %B", Locale::da_DK),
+        "no" | "nb" => ("%-d. %B", Locale::nb_NO),
+        "cs" => ("%-d. %B", Locale::cs_CZ),
+        "fr" => ("%-d %B", Locale::fr_FR),
+        "it" => ("%-d %B", Locale::it_IT),
+        "nl" => ("%-d %B", Locale::nl_NL),
+        "sv" => ("%-d %B", Locale::sv_SE),
+        "pl" => ("%-d %B", Locale::pl_PL),
+        "ru" => ("%-d %B", Locale::ru_RU),
+        "es" => ("%-d de %B", Locale::es_ES),
+        "pt" => ("%-d de %B", Locale::pt_PT),
+        _ => ("%B %-d", Locale::en_US),
+    };
+    date.format_localized(pattern, locale).to_string()
+}
+
+/// The table for one category of entries.
+fn build_table(
+    args: &HistoryArgs,
+    event_type: EventType,
+    response: &OnThisDayResponse,
+    width: usize,
+    (month, day): (u32, u32),
+    current_year: i32,
+) -> Table {
+    let theme = args.theme.theme();
+    let show_age = args.show_age && event_type == EventType::Deaths;
+    let mut table = Table::new();
+    table
+        .load_preset(args.preset.preset())
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_width(width as u16);
+
+    // `--numbered` leads every row with its 1-based position (left blank
+    // on placeholder rows)
+    let numbered = |position: Option<usize>, row: Vec<Cell>| -> Vec<Cell> {
+        let number = position.map(|i| (i + 1).to_string()).unwrap_or_default();
+        let number = args.numbered.then(|| Cell::new(number));
+        number.into_iter().chain(row).collect()
+    };
+    let numbered_header = |header: Vec<Cell>| -> Vec<Cell> {
+        let number = args.numbered.then(|| theme.header_cell(event_type, "#"));
+        number.into_iter().chain(header).collect()
+    };
+    let number_width = if args.numbered { 6 } else { 0 };
+
+    match event_type {
+        EventType::Holidays => {
+            let title = "Holidays & Observances";
+            table.set_header(numbered_header(vec![
+                theme.header_cell(event_type, title),
+            ]));
+            if response.holidays.is_empty() {
+                let text = "No holidays found for this day.";
+                table.add_row(numbered(None, vec![Cell::new(text)]));
+            } else {
+                let text_width = text_wrap_width(
+                    width,
+                    5 + number_width,
+                    args.max_text_width,
+                );
+                for (i, holiday) in response.holidays.iter().enumerate() {
+                    let text = args.fit_text(&holiday.text, text_width);
+                    table.add_row(numbered(Some(i), vec![Cell::new(text)]));
+                }
+            }
+        }
+        _ => {
+            let (header1, header2, events) = match event_type {
+                EventType::Events => ("Year", "Event", &response.events),
+                EventType::Births => ("Born", "Person", &response.births),
+                EventType::Deaths => ("Died", "Person", &response.deaths),
+                EventType::Selected => ("Year", "Event", &response.selected),
+                EventType::Anniversaries => ("Year", "Event", &response.events),
+                EventType::Holidays
+                | EventType::All
+                | EventType::Featured => unreachable!(),
+            };
+            // Anniversaries list the (year-less) holidays after the events
+            let holidays: &[Holiday] = match event_type {
+                EventType::Anniversaries => &response.holidays,
+                _ => &[],
+            };
+
+            let header: Vec<Cell> = args
+                .columns()
+                .iter()
+                .map(|col| match col {
+                    Column::Year => header1,
+                    Column::Event => header2,
+                })
+                .chain(show_age.then_some("Age"))
+                .chain(args.context.then_some("Date"))
+                .map(|name| theme.header_cell(event_type, name))
+                .collect();
+            table.set_header(numbered_header(header));
+
+            // Borders, plus whichever columns sit beside the text
+            let mut reserved = 5 + number_width;
+            if args.columns().contains(&Column::Year) {
+                reserved += 10;
+            }
+            if show_age {
+                reserved += 7;
+            }
+            if args.context {
+                // Its longest cell, plus padding and a border
+                let longest = events
+                    .iter()
+                    .map(|ev| weekday_context(ev.year, month, day))
+                    .map(|cell| textwrap::core::display_width(&cell))
+                    .max()
+                    .unwrap_or(0);
+                reserved += longest.max("Date".len()) + 3;
+            }
+            let text_width =
+                text_wrap_width(width, reserved, args.max_text_width);
+            let links = args.links && std::io::stdout().is_terminal();
+            if events.is_empty() && holidays.is_empty() {
+                let mut row: Vec<Cell> = args
+                    .columns()
+                    .iter()
+                    .map(|col| match col {
+                        Column::Year => Cell::new("N/A"),
+                        Column::Event => {
+                            Cell::new(args.no_entries_text(month, day))
+                        }
+                    })
+                    .collect();
+                if show_age {
+                    row.push(Cell::new(""));
+                }
+                if args.context {
+                    row.push(Cell::new(""));
+                }
+                table.add_row(numbered(None, row));
+            }
+
+            for (i, ev) in events.iter().rev().enumerate() {
+                let anniversary = args
+                    .anniversaries
+                    .then(|| round_anniversary(current_year, ev.year))
+                    .flatten();
+                let mut row: Vec<Cell> = args
+                    .columns()
+                    .iter()
+                    .map(|col| match col {
+                        Column::Year => theme.year_cell(event_type, ev.year),
+                        Column::Event => {
+                            // One-line rows take the marker inline, ahead
+                            // of the cut, instead of on a line of its own
+                            let text = match anniversary {
+                                Some(years) if args.truncate => {
+                                    inline_anniversary(&ev.text, years)
+                                }
+                                _ => ev.text.clone(),
+                            };
+                            let text = args.fit_text(&text, text_width);
+                            let text = match &ev.url {
+                                Some(url) if links => hyperlink(&text, url),
+                                _ => text,
+                            };
+                            match anniversary {
+                                Some(years) if !args.truncate => Cell::new(
+                                    format!("{text}\n⭐ {years} years ago"),
+                                ),
+                                _ => Cell::new(text),
+                            }
+                        }
+                    })
+                    .collect();
+                if show_age {
+                    let age = age_at_death(ev);
+                    row.push(Cell::new(
+                        age.map(|age| age.to_string()).unwrap_or_default(),
+                    ));
+                }
+                if args.context {
+                    row.push(Cell::new(weekday_context(ev.year, month, day)));
+                }
+                table.add_row(numbered(Some(i), row));
+            }
+
+            for (i, holiday) in holidays.iter().enumerate() {
+                let row = args
+                    .columns()
+                    .iter()
+                    .map(|col| match col {
+                        Column::Year => Cell::new(""),
+                        Column::Event => {
+                            Cell::new(args.fit_text(&holiday.text, text_width))
+                        }
+                    })
+                    .collect();
+                table.add_row(numbered(Some(events.len() + i), row));
+            }
+        }
+    }
+
+    // Numbers line up on their last digit; text goes where `--align` says
+    let text = args.align.cell_alignment();
+    let number = args.numbered.then_some(CellAlignment::Right);
+    let alignments: Vec<CellAlignment> = match event_type {
+        EventType::Holidays => number.into_iter().chain([text]).collect(),
+        _ => number
+            .into_iter()
+            .chain(args.columns().iter().map(|col| match col {
+                Column::Year => CellAlignment::Right,
+                Column::Event => text,
+            }))
+            .chain(show_age.then_some(CellAlignment::Right))
+            .chain(args.context.then_some(CellAlignment::Left))
+            .collect(),
+    };
+    for (column, alignment) in table.column_iter_mut().zip(alignments) {
+        column.set_cell_alignment(alignment);
+    }
+
+    table
+}
+
+/// `--plain` lines for one section: the `--columns` fields of each entry,
+/// led by the section name when `titled`, joined by `--separator`. With
+/// `--anniversaries`, a round year's text starts with its marker.
+fn plain_lines(
+    args: &HistoryArgs,
+    event_type: EventType,
+    response: &OnThisDayResponse,
+    titled: bool,
+    current_year: i32,
+) -> Vec<String> {
+    let section = titled.then(|| event_type.to_string());
+    let (events, holidays): (&[Event], &[Holiday]) = match event_type {
+        EventType::Holidays => (&[], &response.holidays),
+        EventType::Anniversaries => (&response.events, &response.holidays),
+        _ => (response.events_of(event_type).unwrap_or_default(), &[]),
+    };
+    let line = |year: Option<i32>, text: &str| {
+        let fields = args.columns().iter().filter_map(|col| match col {
+            Column::Year if event_type == EventType::Holidays => None,
+            Column::Year => {
+                Some(year.map(|year| year.to_string()).unwrap_or_default())
+            }
+            Column::Event => Some(text.to_string()),
+        });
+        let fields: Vec<String> =
+            section.clone().into_iter().chain(fields).collect();
+        fields.join(&args.separator)
+    };
+    events
+        .iter()
+        .rev()
+        .map(|ev| {
+            let anniversary = args
+                .anniversaries
+                .then(|| round_anniversary(current_year, ev.year))
+                .flatten();
+            match anniversary {
+                Some(years) => {
+                    line(Some(ev.year), &inline_anniversary(&ev.text, years))
+                }
+                None => line(Some(ev.year), &ev.text),
+            }
+        })
+        .chain(holidays.iter().map(|holiday| line(None, &holiday.text)))
+        .collect()
+}
+
+/// “1969-07-20 (Sunday)” for an entry's date, or a dash before 1583
+/// (chrono's proleptic Gregorian weekdays would mislead for Julian-era
+/// dates) and for a Feb 29 its year didn't have.
+fn weekday_context(year: i32, month: u32, day: u32) -> String {
+    match NaiveDate::from_ymd_opt(year, month, day) {
+        Some(date) if year >= 1583 => date.format("%Y-%m-%d (%A)").to_string(),
+        _ => "—".to_string(),
+    }
+}
+
+/// How many years lie between `year` and `current_year` when that's a
+/// round anniversary: 25, 50, 75, 100, …
+fn round_anniversary(current_year: i32, year: i32) -> Option<i32> {
+    let years = current_year - year;
+    (years > 0 && years % 25 == 0).then_some(years)
+}
+
+/// `text` led by a short `⭐ 100y` marker, for layouts that keep each
+/// entry on one line.
+fn inline_anniversary(text: &str, years: i32) -> String {
+    format!("⭐ {years}y {text}")
+}
+
+/// One bar per century (oldest first) sized relative to the busiest one,
+/// e.g. `    20th █████████████░░░  12`.
+fn century_histogram(events: &[Event], theme: &Theme) -> String {
+    if events.is_empty() {
+        return "No entries of this type found for this day.".to_string();
+    }
+
+    let mut counts = std::collections::BTreeMap::new();
+    for ev in events {
+        *counts.entry(century_of(ev.year)).or_insert(0usize) += 1;
+    }
+
+    let max = counts.values().copied().max().unwrap_or(1);
+    counts
+        .into_iter()
+        .map(|(century, count)| {
+            let label = if century > 0 {
+                ordinal(century.unsigned_abs())
+            } else {
+                format!("{} BC", ordinal(century.unsigned_abs()))
+            };
+            let percent = count as f64 / max as f64 * 100.0;
+            let bar = chart_bar(percent, theme);
+            format!("{label:>8} {bar} {count:>3}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// One bar per category sized relative to the biggest one, e.g.
+/// `Events   ██████████████████████████████  42`.
+fn type_counts_chart(response: &OnThisDayResponse, theme: &Theme) -> String {
+    let counts = [
+        ("Events", response.events.len()),
+        ("Births", response.births.len()),
+        ("Deaths", response.deaths.len()),
+        ("Holidays", response.holidays.len()),
+    ];
+    let max = counts.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    counts
+        .iter()
+        .map(|(label, count)| {
+            let percent = *count as f64 / max.max(1) as f64 * 100.0;
+            let bar = chart_bar(percent, theme);
+            format!("{label:<8} {bar} {count:>3}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A 30-column bar in `theme`'s bar colour; plain when the theme has no
+/// styling (mono, or colour turned off).
+fn chart_bar(percent: f64, theme: &Theme) -> String {
+    ascii_bar(percent, 30, BarStyle::Blocks, theme.bar, theme.decorate)
+}
+
+/// Signed century of `year`, counted the way the centuries are named: 20
+/// for 1901–2000, -1 for 100 BC–1 BC (the feed's year 0 included).
+fn century_of(year: i32) -> i32 {
+    if year > 0 {
+        (year - 1) / 100 + 1
+    } else {
+        -((-year).max(1) - 1) / 100 - 1
+    }
+}
+
+/// `--group-by-century` buckets of `events`, labelled like “20th century”
+/// (or “1st century BC”), oldest century first unless `newest_first`. Each
+/// bucket is in feed order, newest first, as [`build_table`] expects.
+fn century_groups(
+    events: &[Event],
+    newest_first: bool,
+) -> Vec<(String, Vec<Event>)> {
+    let mut groups: BTreeMap<i32, Vec<Event>> = BTreeMap::new();
+    for ev in events {
+        groups.entry(century_of(ev.year)).or_default().push(ev.clone());
+    }
+    let mut groups: Vec<(String, Vec<Event>)> = groups
+        .into_iter()
+        .map(|(century, mut events)| {
+            if newest_first {
+                events.sort_by_key(|ev| ev.year);
+            } else {
+                events.sort_by_key(|ev| std::cmp::Reverse(ev.year));
+            }
+            let era = if century > 0 { "" } else { " BC" };
+            let label =
+                format!("{} century{era}", ordinal(century.unsigned_abs()));
+            (label, events)
+        })
+        .collect();
+    if newest_first {
+        groups.reverse();
+    }
+    groups
+}
+
+/// `1st`, `2nd`, `3rd`, `4th`, …, `11th`, `21st`.
+fn ordinal(n: u32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+/// Width available for the history table: the `--width` override if given,
+/// otherwise the detected terminal width (80 when it can't be detected).
+fn table_width(width_override: Option<u16>) -> usize {
+    width_override
+        .map(usize::from)
+        .unwrap_or_else(termwidth)
+        .max(50) // sensible minimum
+}
+
+/// Wrap width for entry text in a table `table_width` columns wide whose
+/// borders and other columns take up `reserved`, capped at `max_text_width`.
+/// Never below 20 columns, however little room the other columns leave.
+fn text_wrap_width(
+    table_width: usize,
+    reserved: usize,
+    max_text_width: Option<u16>,
+) -> usize {
+    let width = table_width.saturating_sub(reserved).max(20);
+    max_text_width.map_or(width, |max| width.min(usize::from(max)))
+}
+
+/// `text` on one line of at most `width` display columns, ending in `…`
+/// when it had to be cut; wide characters count double and are never
+/// split.
+fn truncate_to_width(text: &str, width: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if textwrap::core::display_width(&text) <= width {
+        return text;
+    }
+    let mut cut = String::new();
+    let mut used = 1; // the ellipsis
+    for c in text.chars() {
+        let columns = textwrap::core::display_width(c.encode_utf8(&mut [0; 4]));
+        if used + columns > width {
+            break;
+        }
+        used += columns;
+        cut.push(c);
+    }
+    format!("{}…", cut.trim_end())
+}
+
+/// Wrap every line of `text` in an OSC 8 hyperlink to `url`, so the link
+/// survives the table splitting the cell into lines.
+fn hyperlink(text: &str, url: &str) -> String {
+    text.lines()
+        .map(|line| format!("\x1b]8;;{url}\x1b\\{line}\x1b]8;;\x1b\\"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Birth year stated in a death entry such as
+/// “Agnès Varda, Belgian-born French director (b. 1928)”.
+fn birth_year(text: &str) -> Option<i32> {
+    ["(b. ", "(born "].iter().find_map(|marker| {
+        let rest = &text[text.rfind(marker)? + marker.len()..];
+        rest[..rest.find(')')?].trim().parse().ok()
+    })
+}
+
+/// Age in years reached in the year of death, if the entry gives a birth
+/// year and the result is plausible.
+fn age_at_death(ev: &Event) -> Option<i32> {
+    let age = ev.year - birth_year(&ev.text)?;
+    (0..=130).contains(&age).then_some(age)
+}
+
+/* --------------------------------------------------------------------------
+ *                                 feeds
+ * ---------------------------------------------------------------------- */
+
+/// Render the requested entries as an RSS 2.0 or Atom document with one
+/// item per entry, titled by year (holidays use their text as title).
+/// Links point at the wiki that served the entries, which is a
+/// `--fallback` one when `--language` had none; Atom's `updated` is `now`.
+fn render_feed(
+    format: FeedFormat,
+    args: &HistoryArgs,
+    fetched: &Fetched,
+    (month, day): (u32, u32),
+    now: DateTime<FixedOffset>,
+) -> String {
+    let date = NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+    let title = format!(
+        "On This Day: {} ({})",
+        date.format("%B %-d"),
+        args.event_type(),
+    );
+    let link = format!("https://{}.wikipedia.org/", fetched.lang);
+    let response = &fetched.response;
+
+    // (title, description) pairs in display order
+    let entries: Vec<(String, String)> =
+        match response.events_of(args.event_type()) {
+            Some(events) => events
+                .iter()
+                .rev()
+                .map(|ev| (ev.year.to_string(), ev.text.clone()))
+                .collect(),
+            None => response
+                .holidays
+                .iter()
+                .map(|holiday| (holiday.text.clone(), holiday.text.clone()))
+                .collect(),
+        };
+
+    match format {
+        FeedFormat::Rss => {
+            let items = entries
+                .into_iter()
+                .map(|(title, description)| {
+                    rss::ItemBuilder::default()
+                        .title(Some(title))
+                        .description(Some(description))
+                        .build()
+                })
+                .collect::<Vec<_>>();
+            rss::ChannelBuilder::default()
+                .title(title)
+                .link(link)
+                .description("Wikipedia “On This Day” entries")
+                .items(items)
+                .build()
+                .to_string()
+        }
+        FeedFormat::Atom => {
+            let entries = entries
+                .into_iter()
+                .enumerate()
+                .map(|(i, (title, description))| {
+                    atom_syndication::EntryBuilder::default()
+                        .id(format!("{link}#{month:02}-{day:02}-{i}"))
+                        .title(title)
+                        .summary(Some(atom_syndication::Text::plain(
+                            description,
+                        )))
+                        .updated(now)
+                        .build()
+                })
+                .collect::<Vec<_>>();
+            atom_syndication::FeedBuilder::default()
+                .id(link)
+                .title(title)
+                .updated(now)
+                .entries(entries)
+                .build()
+                .to_string()
+        }
+    }
+}
+
+/* --------------------------------------------------------------------------
+ *                              quick picks
+ * ---------------------------------------------------------------------- */
+
+/// “Today in 1969: …” for the newest entry, or “Today: …” with the first
+/// holiday; combined types headline their events.
+fn headline(
+    event_type: EventType,
+    response: &OnThisDayResponse,
+) -> Option<String> {
+    if event_type == EventType::Holidays {
+        let holiday = response.holidays.first()?;
+        return Some(format!("Today: {}", holiday.text));
+    }
+    let events = response
+        .events_of(event_type)
+        .unwrap_or(&response.events);
+    let ev = events.iter().max_by_key(|ev| ev.year)?;
+    Some(format!("Today in {}: {}", ev.year, ev.text))
+}
+
+/// One-line summary of the earliest (or, with `last`, the latest) entry.
+/// Year-bearing entries are ordered by year, holidays alphabetically.
+fn pick_extreme(
+    event_type: EventType,
+    response: &OnThisDayResponse,
+    last: bool,
+) -> Option<String> {
+    match response.events_of(event_type) {
+        Some(events) => {
+            let ev = if last {
+                events.iter().max_by_key(|ev| ev.year)
+            } else {
+                events.iter().min_by_key(|ev| ev.year)
+            }?;
+            Some(format!("{} — {}", ev.year, ev.text))
+        }
+        None => {
+            let mut texts: Vec<&str> = response
+                .holidays
+                .iter()
+                .map(|holiday| holiday.text.as_str())
+                .collect();
+            texts.sort_by_cached_key(|text| text.to_lowercase());
+            let text = if last { texts.last() } else { texts.first() }?;
+            Some(text.to_string())
+        }
+    }
+}
+
+/// The history part of `today`: the newest few events, as `history`
+/// shows them. A failed fetch only prints a note, so the rest of the
+/// dashboard stands.
+async fn show_today_summary(
+    args: &TodayArgs,
+    now: DateTime<FixedOffset>,
+    wiki: &Wiki,
+) -> Result<()> {
+    let limit = args.limit.to_string();
+    let date = now.date_naive().to_string();
+    let history = HistoryArgs::try_parse_from([
+        "history",
+        "--date",
+        &date,
+        "--language",
+        &args.language,
+        "--theme",
+        args.theme.to_possible_value().unwrap().get_name(),
+        "--sort",
+        "year",
+        "--reverse",
+        "--limit",
+        &limit,
+        "--no-pager",
+    ])?;
+    if let Err(err) = show_on_this_day(&history, now, wiki).await {
+        println!("(Events unavailable right now: {err})");
+    }
+    Ok(())
+}
+
+/* --------------------------------------------------------------------------
+ *                            day comparison
+ * ---------------------------------------------------------------------- */
+
+/// Number of entries per single category, in [`EventType::All`] order.
+fn category_counts(response: &OnThisDayResponse) -> Vec<(EventType, usize)> {
+    EventType::All
+        .parts()
+        .iter()
+        .map(|part| {
+            let count = response
+                .events_of(*part)
+                .map_or(response.holidays.len(), <[Event]>::len);
+            (*part, count)
+        })
+        .collect()
+}
+
+/// Side-by-side entry counts for two days, with ▲/▼ on the larger and
+/// smaller side of each row.
+async fn show_day_diff(
+    args: &DiffArgs,
+    color: bool,
+    wiki: &Wiki,
+) -> Result<()> {
+    let fetch = |date: NaiveDate| {
+        let lang = &args.language;
+        wiki.client.fetch_day(lang, EventType::All, date.month(), date.day())
+    };
+    let (first, second) =
+        tokio::try_join!(fetch(args.first), fetch(args.second))?;
+    wiki.report(&first);
+    wiki.report(&second);
+
+    let first_counts = category_counts(&first.response);
+    let second_counts = category_counts(&second.response);
+    let total = |counts: &[(EventType, usize)]| {
+        counts.iter().map(|(_, count)| count).sum::<usize>()
+    };
+    let rows = first_counts
+        .iter()
+        .zip(&second_counts)
+        .map(|((part, a), (_, b))| (part.title(), *a, *b))
+        .chain([("Total", total(&first_counts), total(&second_counts))]);
+
+    let style = |cell: Cell, fg: Option<Color>, bold: bool| {
+        if color {
+            Theme::style(cell, fg, bold)
+        } else {
+            cell
+        }
+    };
+    let mut table = Table::new();
+    table.load_preset(presets::UTF8_FULL).set_header(vec![
+        style(Cell::new("Category"), None, true),
+        style(Cell::new(header_day(args.first, &args.language)), None, true),
+        style(Cell::new(header_day(args.second, &args.language)), None, true),
+    ]);
+    for (title, a, b) in rows {
+        let side = |count: usize, other: usize| {
+            let (mark, fg) = match count.cmp(&other) {
+                std::cmp::Ordering::Greater => (" ▲", Some(Color::Green)),
+                std::cmp::Ordering::Less => (" ▼", Some(Color::Red)),
+                std::cmp::Ordering::Equal => ("", None),
+            };
+            style(Cell::new(format!("{count}{mark}")), fg, false)
+        };
+        table.add_row(vec![Cell::new(title), side(a, b), side(b, a)]);
+    }
+
+    println!("{table}");
+    Ok(())
+}
+
+/* --------------------------------------------------------------------------
+ *                              time output
+ * ---------------------------------------------------------------------- */
+
+fn show_current_time(
+    now: DateTime<FixedOffset>,
+    format: Option<&ClockFormat>,
+    color: bool,
+) {
+    let preset = match format {
+        None => ClockPreset::Human,
+        Some(ClockFormat::Preset(preset)) => *preset,
+        Some(ClockFormat::Strftime(format)) => {
+            println!("{}", now.format(format));
+            return;
+        }
+    };
+    match preset {
+        ClockPreset::Human => println!(
+            "{}\n{}",
+            paint("The current time is:", Style::new().bold(), color),
+            now.format("%A, %B %d, %Y %r"),
+        ),
+        ClockPreset::Iso => println!("{}", now.to_rfc3339()),
+        ClockPreset::Rfc2822 => println!("{}", now.to_rfc2822()),
+        ClockPreset::Unix => println!("{}", now.timestamp()),
+        ClockPreset::Short => println!("{}", now.format("%Y-%m-%d %H:%M")),
+    }
+}
+
+/// `--watch`: clear the screen and redraw the clock (or the statistics)
+/// every `--interval` until Ctrl-C.
+async fn watch(cli: &Cli) -> Result<()> {
+    let interval = Duration::from_millis(cli.interval);
+    loop {
+        let now = now_in(cli.zone(), clock()?);
+        print!("\x1b[2J\x1b[H"); // clear the screen, cursor to the top
+        if cli.statistics {
+            show_time_statistics(now, cli)?;
+        } else {
+            let format = cli.clock_format(cli.format.as_ref());
+            show_current_time(now, format.as_ref(), !cli.no_color);
+        }
+        std::io::stdout().flush()?;
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// The current time as pretty JSON: RFC 3339, unix seconds and offset.
+fn now_json(now: DateTime<FixedOffset>) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&serde_json::json!({
+        "date": now.to_rfc3339(),
+        "unix_timestamp": now.timestamp(),
+        "utc_offset": now.offset().to_string(),
+    }))?)
+}
+
+/// A `width`-character bar `percent` full; without `color_enabled` it's
+/// plain characters, with no colour or dimming.
+fn ascii_bar(
+    percent: f64,
+    width: usize,
+    style: BarStyle,
+    color: BarColor,
+    color_enabled: bool,
+) -> String {
+    let (filled_char, empty_char) = style.chars();
+    let filled = ((percent / 100.0) * width as f64).round() as usize;
+    let empty = width.saturating_sub(filled);
+
+    let filled = filled_char.to_string().repeat(filled);
+    let empty = empty_char.to_string().repeat(empty);
+    if !color_enabled {
+        return format!("{filled}{empty}");
+    }
+    let filled = match color.ansi() {
+        Some(ansi) => filled.color(ansi).to_string(),
+        None => filled,
+    };
+    format!("{filled}{}", empty.dimmed())
+}
+
+/// `text` in `style`, or plain when colour is off.
+fn paint(text: impl std::fmt::Display, style: Style, color: bool) -> String {
+    if color {
+        text.style(style).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/* --------------------------------------------------------------------------
+ *                            date arithmetic
+ * ---------------------------------------------------------------------- */
+
+/// Move `at` by `by`, forwards or backwards. Months go first and clamp to
+/// the end of shorter months (Jan 31 + 1 month → Feb 28/29), then days,
+/// then the sub-day remainder.
+fn shift(
+    at: DateTime<FixedOffset>,
+    by: HumanDuration,
+    backwards: bool,
+) -> Option<DateTime<FixedOffset>> {
+    let months = Months::new(by.months);
+    let days = Days::new(by.days);
+    let rest = TimeDelta::seconds(by.seconds);
+
+    if backwards {
+        at.checked_sub_months(months)?
+            .checked_sub_days(days)?
+            .checked_sub_signed(rest)
+    } else {
+        at.checked_add_months(months)?
+            .checked_add_days(days)?
+            .checked_add_signed(rest)
+    }
+}
+
+fn show_shifted(
+    args: &ShiftArgs,
+    date_fmt: Option<&str>,
+    backwards: bool,
+) -> Result<()> {
+    let Some(result) = shift(args.date.at, args.duration, backwards) else {
+        bail!("the resulting date is out of range");
+    };
+
+    let default = match (args.date.date_only, args.duration.seconds) {
+        (true, 0) => "%Y-%m-%d",
+        (true, _) => "%Y-%m-%d %H:%M:%S",
+        (false, _) => "%Y-%m-%dT%H:%M:%S%:z",
+    };
+    let format = date_fmt.or(args.format.as_deref()).unwrap_or(default);
+    println!("{}", result.format(format));
+    Ok(())
+}
+
+/// Stand-in for Feb 29 in common years.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[value(rename_all = "lower")]
+enum LeapDay {
+    Feb28,
+    Mar1,
+}
+
+/// Days from `today` until `date`'s month and day next come round (0 if
+/// that's today). Feb 29 moves to `leap_day` in common years.
+fn days_until(today: NaiveDate, date: NaiveDate, leap_day: LeapDay) -> i64 {
+    let occurrence = |year: i32| {
+        NaiveDate::from_ymd_opt(year, date.month(), date.day())
+            .or_else(|| match leap_day {
+                LeapDay::Feb28 => NaiveDate::from_ymd_opt(year, 2, 28),
+                LeapDay::Mar1 => NaiveDate::from_ymd_opt(year, 3, 1),
+            })
+            .expect("every MM-DD exists or falls back")
+    };
+    let mut next = occurrence(today.year());
+    if next < today {
+        next = occurrence(today.year() + 1);
+    }
+    (next - today).num_days()
+}
+
+/// “3 hours ago” / “in 2 days” for an offset from now in seconds, using
+/// the largest whole unit (a month is 30 days, a year 365).
+fn relative_time(seconds: i64) -> String {
+    const UNITS: [(&str, i64); 7] = [
+        ("year", 365 * 86_400),
+        ("month", 30 * 86_400),
+        ("week", 7 * 86_400),
+        ("day", 86_400),
+        ("hour", 3600),
+        ("minute", 60),
+        ("second", 1),
+    ];
+
+    let abs = seconds.unsigned_abs();
+    let Some((unit, count)) = UNITS.iter().find_map(|&(unit, size)| {
+        let count = abs / size as u64;
+        (count > 0).then_some((unit, count))
+    }) else {
+        return "just now".to_string();
+    };
+
+    let plural = if count == 1 { "" } else { "s" };
+    if seconds < 0 {
+        format!("{count} {unit}{plural} ago")
+    } else {
+        format!("in {count} {unit}{plural}")
+    }
+}
+
+/* --------------------------------------------------------------------------
+ *                              favorites
+ * ---------------------------------------------------------------------- */
+
+/// Bookmarked days by name, kept on disk as `{"name": "MM-DD", …}`.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(transparent)]
+struct Favorites {
+    days: BTreeMap<String, String>,
+}
+
+/// The favorites file, locked against other runs until dropped.
+struct FavoritesFile {
+    path: PathBuf,
+    lock: PathBuf,
+}
+
+/// How long `favorites` waits for another run to finish with the file.
+const FAVORITES_LOCK_WAIT: Duration = Duration::from_secs(5);
+
+/// A lock file older than this was left behind by a run that died.
+const FAVORITES_LOCK_STALE: Duration = Duration::from_secs(30);
+
+impl FavoritesFile {
+    /// Lock the favorites file and read it; a missing file is an empty
+    /// list, and one that won't parse is moved aside to `.bak` so the
+    /// list can start afresh.
+    async fn open() -> Result<(Self, Favorites)> {
+        let dir = config_dir()
+            .ok_or_else(|| anyhow!("no config directory for favorites"))?;
+        std::fs::create_dir_all(&dir)?;
+        let file = FavoritesFile {
+            path: dir.join("favorites.json"),
+            lock: dir.join("favorites.json.lock"),
+        };
+        file.acquire().await?;
+
+        let raw = match std::fs::read_to_string(&file.path) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                return Ok((file, Favorites::default()));
+            }
+            Err(err) => return Err(err.into()),
+        };
+        match serde_json::from_str::<Favorites>(&raw) {
+            Ok(favorites)
+                if favorites
+                    .days
+                    .values()
+                    .all(|day| parse_month_day(day).is_ok()) =>
+            {
+                Ok((file, favorites))
+            }
+            _ => {
+                let backup = file.path.with_extension("json.bak");
+                std::fs::rename(&file.path, &backup)?;
+                eprintln!(
+                    "Warning: {} was unreadable; moved it to {} and started \
+                     a new list",
+                    file.path.display(),
+                    backup.display(),
+                );
+                Ok((file, Favorites::default()))
+            }
+        }
+    }
+
+    /// Create the lock file, waiting out another run that holds it; the
+    /// wait yields to the runtime rather than blocking its only thread.
+    async fn acquire(&self) -> Result<()> {
+        let deadline = Instant::now() + FAVORITES_LOCK_WAIT;
+        loop {
+            let created = std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&self.lock);
+            match created {
+                Ok(_) => return Ok(()),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+                Err(err) => return Err(err.into()),
+            }
+            let age = std::fs::metadata(&self.lock)
+                .and_then(|meta| meta.modified())
+                .map(|modified| modified.elapsed().unwrap_or_default());
+            if age.is_ok_and(|age| age > FAVORITES_LOCK_STALE) {
+                let _ = std::fs::remove_file(&self.lock);
+            } else if Instant::now() >= deadline {
+                bail!(
+                    "the favorites are in use by another run (remove {} if \
+                     none is)",
+                    self.lock.display(),
+                );
+            } else {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        }
+    }
+
+    /// Replace the file in one step, so a crash or a reader never sees
+    /// half a list.
+    fn save(&self, favorites: &Favorites) -> Result<()> {
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(favorites)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl Drop for FavoritesFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.lock);
+    }
+}
+
+/// Directory for `favorites.json`; `TIME_CLI_CONFIG_DIR` overrides the
+/// platform config directory.
+fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("TIME_CLI_CONFIG_DIR")
+        .map(PathBuf::from)
+        .or_else(|| dirs::config_dir().map(|dir| dir.join("time_cli")))
+}
+
+/// `(month, day)` of the favorite called `name`.
+async fn favorite_day(name: &str) -> Result<(u32, u32)> {
+    let (_file, favorites) = FavoritesFile::open().await?;
+    let Some(day) = favorites.days.get(name) else {
+        bail!("no favorite named '{name}' (see `favorites list`)");
+    };
+    let date = parse_month_day(day).map_err(|err| anyhow!(err))?;
+    Ok((date.month(), date.day()))
+}
+
+async fn show_favorites(action: &FavoritesAction) -> Result<()> {
+    let (file, mut favorites) = FavoritesFile::open().await?;
+    match action {
+        FavoritesAction::Add(args) => {
+            let day = args.date.format("%m-%d").to_string();
+            let name = args.name.as_deref().unwrap_or(&day).trim();
+            if name.is_empty() {
+                bail!("a favorite's name can't be empty");
+            }
+            let old = favorites.days.insert(name.to_string(), day.clone());
+            file.save(&favorites)?;
+            match old {
+                Some(old) if old != day => {
+                    println!("Moved '{name}' from {old} to {day}")
+                }
+                _ => println!("Saved {day} as '{name}'"),
+            }
+        }
+        FavoritesAction::List if favorites.days.is_empty() => {
+            println!("No favorites yet; add one with `favorites add MM-DD`.");
+        }
+        FavoritesAction::List => {
+            let mut days: Vec<_> = favorites.days.iter().collect();
+            days.sort_by_key(|(name, day)| (*day, *name));
+            for (name, day) in days {
+                println!("{day}  {name}");
+            }
+        }
+        FavoritesAction::Remove(args) => {
+            if let Some(day) = favorites.days.remove(&args.target) {
+                file.save(&favorites)?;
+                println!("Removed '{}' ({day})", args.target);
+                return Ok(());
+            }
+            let Ok(date) = parse_month_day(&args.target) else {
+                bail!("no favorite named '{}'", args.target);
+            };
+            let day = date.format("%m-%d").to_string();
+            let before = favorites.days.len();
+            favorites.days.retain(|_, saved| *saved != day);
+            let removed = before - favorites.days.len();
+            if removed == 0 {
+                bail!("no favorites on {day}");
+            }
+            file.save(&favorites)?;
+            let plural = if removed == 1 { "" } else { "s" };
+            println!("Removed {removed} favorite{plural} on {day}");
+        }
+    }
+    Ok(())
+}
+
+/* --------------------------------------------------------------------------
  *                            time statistics
  * ---------------------------------------------------------------------- */
 
-#[derive(Debug, Copy, Clone)]
-#[must_use]
-struct TimeStats {
-    day_of_year: u32,
-    total_days_in_year: u32,
-    day_progress: f64,  // 0–100
-    year_progress: f64, // 0–100
-    week_of_year: u32,
-    is_leap: bool,
-    unix_timestamp: i64,
-}
-
-fn compute_time_statistics(now: chrono::DateTime<Local>) -> TimeStats {
-    let year = now.year();
-    let is_leap = NaiveDate::from_ymd_opt(year, 2, 29).is_some();
-
-    let seconds_into_day =
-        now.hour() * 3600 + now.minute() * 60 + now.second();
-    let seconds_in_day = 86_400;
-    let day_progress =
-        (seconds_into_day as f64 / seconds_in_day as f64) * 100.0;
-
-    let day_of_year = now.ordinal();
-    let total_days_in_year = if is_leap { 366 } else { 365 };
-    let year_progress =
-        (day_of_year as f64 / total_days_in_year as f64) * 100.0;
-
-    TimeStats {
-        day_of_year,
-        total_days_in_year,
-        day_progress,
-        year_progress,
-        week_of_year: now.iso_week().week(),
-        is_leap,
-        unix_timestamp: now.timestamp(),
-    }
-}
-
-fn show_time_statistics(now: chrono::DateTime<Local>) {
-    let stats = compute_time_statistics(now);
-    let bar_width = 28;
-
-    println!("\n{}\n{}", "Time statistics".bold(), "─".repeat(35));
-    println!("Date            : {}", now.format("%A, %B %d %Y"));
-    println!("Local time      : {}", now.format("%r"));
-    println!("Unix timestamp  : {}", stats.unix_timestamp);
+fn show_time_statistics(now: DateTime<FixedOffset>, cli: &Cli) -> Result<()> {
+    let stats = compute_time_statistics(now, cli.zone());
+    if cli.json {
+        println!("{}", statistics_json(now, &stats)?);
+    } else if cli.prometheus {
+        print!("{}", prometheus_statistics(&stats));
+    } else if cli.compact {
+        let locale = cli.locale.unwrap_or_default();
+        let precision = cli.progress_precision;
+        println!("{}", compact_statistics(now, &stats, locale, precision));
+    } else {
+        print_full_statistics(now, &stats, cli);
+    }
+    Ok(())
+}
+
+/// `--statistics --json`: the statistics led by the RFC 3339 `date` they're
+/// for.
+#[derive(Serialize, JsonSchema)]
+struct StatsJson<'a> {
+    date: String,
+    #[serde(flatten)]
+    stats: &'a TimeStats,
+}
+
+/// The statistics as pretty JSON.
+fn statistics_json(
+    now: DateTime<FixedOffset>,
+    stats: &TimeStats,
+) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&StatsJson {
+        date: now.to_rfc3339(),
+        stats,
+    })?)
+}
+
+/// JSON Schema (draft 2020-12) for one of the `--json` outputs, derived
+/// from the types that produce it.
+fn json_schema(output: SchemaOutput) -> Result<String> {
+    let generator = SchemaSettings::default().for_serialize().into_generator();
+    let schema = match output {
+        SchemaOutput::History => {
+            generator.into_root_schema_for::<BTreeMap<String, DayJson>>()
+        }
+        SchemaOutput::Statistics => {
+            generator.into_root_schema_for::<StatsJson>()
+        }
+    };
+    Ok(serde_json::to_string_pretty(&schema)?)
+}
+
+/// Two-line summary, e.g.
+/// `Fri 2024-03-01 12:00:00 | unix 1709290800` /
+/// `Day 61/366 50.0% | Week 9 | Year 16.7% | leap`, with `precision`
+/// decimal places in the percentages.
+fn compact_statistics(
+    now: DateTime<FixedOffset>,
+    stats: &TimeStats,
+    locale: NumberLocale,
+    precision: u8,
+) -> String {
+    let precision = usize::from(precision);
+    let percent = |value: f64| locale.number(format!("{value:.precision$}"));
+    format!(
+        "{} | unix {}\nDay {}/{} {}% | Week {} | Year {}% | {}",
+        now.format("%a %Y-%m-%d %H:%M:%S"),
+        stats.unix_timestamp,
+        stats.day_of_year,
+        stats.total_days_in_year,
+        percent(stats.day_progress),
+        stats.week_of_year,
+        percent(stats.year_progress),
+        if stats.is_leap { "leap" } else { "common" },
+    )
+}
+
+/// The statistics as Prometheus gauges, each with `# HELP` / `# TYPE`.
+fn prometheus_statistics(stats: &TimeStats) -> String {
+    let metrics: [(&str, &str, String); 7] = [
+        (
+            "day_progress",
+            "Percentage of the current day elapsed.",
+            format!("{:.3}", stats.day_progress),
+        ),
+        (
+            "year_progress",
+            "Percentage of the current year's days reached.",
+            format!("{:.3}", stats.year_progress),
+        ),
+        (
+            "unix_timestamp",
+            "Seconds since 1970-01-01 00:00 UTC.",
+            stats.unix_timestamp.to_string(),
+        ),
+        (
+            "day_of_year",
+            "Ordinal day of the year (1-based).",
+            stats.day_of_year.to_string(),
+        ),
+        (
+            "days_in_year",
+            "Number of days in the current year.",
+            stats.total_days_in_year.to_string(),
+        ),
+        (
+            "week_of_year",
+            "ISO 8601 week number.",
+            stats.week_of_year.to_string(),
+        ),
+        (
+            "leap_year",
+            "1 if the current year is a leap year, else 0.",
+            u8::from(stats.is_leap).to_string(),
+        ),
+    ];
+
+    let mut out = String::new();
+    for (name, help, value) in metrics {
+        out.push_str(&format!(
+            "# HELP time_cli_{name} {help}\n\
+             # TYPE time_cli_{name} gauge\n\
+             time_cli_{name} {value}\n",
+        ));
+    }
+    out
+}
+
+/// Length of the statistics bars for a `terminal_width`-column terminal:
+/// about a third of it (28 at 80 columns), within 20..=60.
+fn stats_bar_width(terminal_width: usize) -> usize {
+    (terminal_width * 35 / 100).clamp(20, 60)
+}
+
+fn print_full_statistics(
+    now: DateTime<FixedOffset>,
+    stats: &TimeStats,
+    cli: &Cli,
+) {
+    let color = !cli.no_color;
+    let bar_width = stats_bar_width(termwidth());
+    let bar = |percent| {
+        ascii_bar(percent, bar_width, cli.bar_style, cli.bar_color, color)
+    };
+    // right-aligned so "100" lines up with the widest value
+    let precision = usize::from(cli.progress_precision);
+    let width = if precision == 0 { 3 } else { 4 + precision };
+    let locale = cli.locale.unwrap_or_default();
+    let percent =
+        |value: f64| locale.number(format!("{value:>width$.precision$}"));
+    // `--explain` notes, empty otherwise
+    let note = |text: &str| {
+        if cli.explain {
+            paint(format!("  ({text})"), Style::new().dimmed(), color)
+        } else {
+            String::new()
+        }
+    };
+
+    // The zone `now` is shown in: UTC, the `--timezone` one or local
+    let zone = cli
+        .zone()
+        .map_or("your local time zone".to_string(), |tz| tz.to_string());
+
+    let title = paint("Time statistics", Style::new().bold(), color);
+    println!("\n{title}\n{}", "─".repeat(35));
+    println!(
+        "Date            : {}{}",
+        now.format("%A, %B %d %Y"),
+        note(&format!("today's date in {zone}")),
+    );
+    println!(
+        "Local time      : {}{}",
+        now.format("%r"),
+        note(&format!("wall-clock time in {zone}")),
+    );
+    println!(
+        "Unix timestamp  : {}{}",
+        stats.unix_timestamp,
+        note("seconds since 1970-01-01 00:00 UTC"),
+    );
 
     println!(
-        "\nDay   ({}/{}) : {} {:>5.1} %",
+        "\nDay   ({}/{}) : {} {} %{}",
         stats.day_of_year,
         stats.total_days_in_year,
-        ascii_bar(stats.day_progress, bar_width),
-        stats.day_progress,
+        bar(stats.day_progress),
+        percent(stats.day_progress),
+        note("day N of the year; bar: share of today's hours elapsed"),
     );
 
     println!(
-        "Year  (week {}) : {} {:>5.1} %",
+        "Year  (week {}) : {} {} %{}",
         stats.week_of_year,
-        ascii_bar(stats.year_progress, bar_width),
-        stats.year_progress,
+        bar(stats.year_progress),
+        percent(stats.year_progress),
+        note("ISO 8601 week number; bar: share of the year's days reached"),
+    );
+
+    if cli.detailed {
+        println!(
+            "Week            : {} {} %{}",
+            bar(stats.week_progress),
+            percent(stats.week_progress),
+            note("share of the week elapsed, counting from Monday 00:00"),
+        );
+    }
+
+    let minutes_left = stats.seconds_remaining_in_day() / 60;
+    println!(
+        "\nRemaining today : {}h {}m{}",
+        minutes_left / 60,
+        minutes_left % 60,
+        note("until midnight"),
+    );
+    let (days_left, weeks_left) = (
+        stats.days_remaining_in_year(),
+        stats.weeks_remaining_in_year(),
     );
+    let plural = |n: u32| if n == 1 { "" } else { "s" };
+    println!(
+        "Remaining year  : {days_left} day{} ({weeks_left} week{}){}",
+        plural(days_left),
+        plural(weeks_left),
+        note("whole days after today, and the full weeks in them"),
+    );
+
+    if let (Some(lat), Some(lon)) = (cli.lat, cli.lon) {
+        match daylight(now.date_naive(), lat, lon) {
+            Daylight::Span { rise, set } => {
+                let rise = rise.with_timezone(now.offset());
+                let set = set.with_timezone(now.offset());
+                let minutes = (set - rise).num_minutes();
+                println!(
+                    "\nDaylight        : {}–{} ({}h {}m){}",
+                    rise.format("%H:%M"),
+                    set.format("%H:%M"),
+                    minutes / 60,
+                    minutes % 60,
+                    note("sunrise to sunset at --lat/--lon"),
+                );
+                let elapsed = ((now - rise).num_seconds() as f64
+                    / (set - rise).num_seconds() as f64
+                    * 100.0)
+                    .clamp(0.0, 100.0);
+                println!(
+                    "Sun             : {} {} %{}",
+                    bar(elapsed),
+                    percent(elapsed),
+                    note("share of today's daylight elapsed"),
+                );
+            }
+            Daylight::MidnightSun => {
+                println!("\nDaylight        : all day (midnight sun)");
+            }
+            Daylight::PolarNight => {
+                println!("\nDaylight        : none (polar night)");
+            }
+        }
+    }
 
     println!(
-        "\nLeap year       : {}",
+        "\nLeap year       : {}{}",
         if stats.is_leap {
-            "Yes".bright_green().to_string()
+            paint("Yes", Style::new().bright_green(), color)
         } else {
-            "No".bright_red().to_string()
+            paint("No", Style::new().bright_red(), color)
         },
+        note("leap years have 366 days, with February 29"),
     );
 }
 
@@ -435,36 +4170,564 @@ fn show_time_statistics(now: chrono::DateTime<Local>) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
+
     use chrono::TimeZone;
 
     #[test]
-    fn leap_year_statistics() {
-        let dt = Local.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
-        let stats = compute_time_statistics(dt);
-        assert!(stats.is_leap);
-        assert_eq!(stats.total_days_in_year, 366);
-        // 1 March in a leap year is day 61
-        assert_eq!(stats.day_of_year, 61);
+    fn compact_statistics_layout() {
+        let dt = Local
+            .with_ymd_and_hms(2024, 3, 1, 12, 0, 0)
+            .unwrap()
+            .fixed_offset();
+        let stats = compute_time_statistics(dt, None);
+        let compact =
+            compact_statistics(dt, &stats, NumberLocale::default(), 1);
+
+        let lines: Vec<&str> = compact.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("Fri 2024-03-01 12:00:00 | unix "));
+        assert_eq!(lines[1], "Day 61/366 50.0% | Week 9 | Year 16.7% | leap");
+
+        let whole = compact_statistics(dt, &stats, NumberLocale::default(), 0);
+        assert!(whole.ends_with("Day 61/366 50% | Week 9 | Year 17% | leap"));
+    }
+
+    #[test]
+    fn ascii_bar_fill_is_independent_of_style() {
+        for style in [BarStyle::Blocks, BarStyle::Ascii, BarStyle::Arrows] {
+            let (filled_char, empty_char) = style.chars();
+            let cases = [(0.0, 28), (16.7, 28), (50.0, 10), (100.0, 7)];
+            for (percent, width) in cases {
+                let bar =
+                    ascii_bar(percent, width, style, BarColor::Cyan, true);
+                let expected = ((percent / 100.0) * width as f64).round();
+                let count = |ch| bar.chars().filter(|&c| c == ch).count();
+                let (filled, empty) = (count(filled_char), count(empty_char));
+                assert_eq!(filled, expected as usize, "{style:?} {percent}");
+                assert_eq!(filled + empty, width);
+            }
+        }
     }
 
     #[test]
-    fn non_leap_year() {
-        let dt = Local.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
-        let stats = compute_time_statistics(dt);
-        assert!(!stats.is_leap);
-        assert_eq!(stats.total_days_in_year, 365);
+    fn now_in_zone_moves_the_date() {
+        let utc = Utc.with_ymd_and_hms(2024, 3, 1, 23, 30, 0).unwrap();
+        let tokyo = now_in(Some(parse_timezone("Asia/Tokyo").unwrap()), utc);
+        assert_eq!(tokyo.to_rfc3339(), "2024-03-02T08:30:00+09:00");
+        let in_utc = now_in(Some(Tz::UTC), utc);
+        assert_eq!(in_utc.date_naive().to_string(), "2024-03-01");
+        assert!(parse_timezone("Mars/Olympus_Mons").is_err());
     }
 
     #[test]
     fn parse_lang_code_ok() {
         assert_eq!(parse_lang_code("de").unwrap(), "de");
         assert_eq!(parse_lang_code("EN").unwrap(), "en");
+        assert_eq!(parse_lang_code("nah").unwrap(), "nah");
+        assert_eq!(parse_lang_code("zh-YUE").unwrap(), "zh-yue");
+        assert_eq!(parse_lang_code("zh-min-nan").unwrap(), "zh-min-nan");
     }
 
     #[test]
     fn parse_lang_code_err() {
-        assert!(parse_lang_code("eng").is_err());
-        assert!(parse_lang_code("1a").is_err());
+        for code in ["e", "engl", "1a", "zh-", "zh--yue", "en.x", "ü"] {
+            assert!(parse_lang_code(code).is_err(), "accepted {code:?}");
+        }
+    }
+
+    #[test]
+    fn stats_bar_width_follows_the_terminal() {
+        assert_eq!(stats_bar_width(80), 28);
+        assert_eq!(stats_bar_width(120), 42);
+        assert_eq!(stats_bar_width(40), 20);
+        assert_eq!(stats_bar_width(300), 60);
+    }
+
+    #[test]
+    fn table_width_override_and_minimum() {
+        assert_eq!(table_width(Some(60)), 60);
+        assert_eq!(table_width(Some(10)), 50);
+    }
+
+    fn sample_response() -> OnThisDayResponse {
+        serde_json::from_str(
+            r#"{
+                "events": [
+                    {"year": 1969, "text": "Apollo 11 lands on the Moon."},
+                    {"year": 2001, "text": "Something recent."},
+                    {"year": 1492, "text": "Something old."}
+                ],
+                "holidays": [{"text": "moon Day"}, {"text": "Armed Forces"}]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn pick_extreme_by_year_and_alphabet() {
+        let response = sample_response();
+        assert_eq!(
+            pick_extreme(EventType::Events, &response, false).unwrap(),
+            "1492 — Something old.",
+        );
+        assert_eq!(
+            pick_extreme(EventType::Events, &response, true).unwrap(),
+            "2001 — Something recent.",
+        );
+        assert_eq!(
+            pick_extreme(EventType::Holidays, &response, false).unwrap(),
+            "Armed Forces",
+        );
+        assert!(pick_extreme(EventType::Births, &response, true).is_none());
+    }
+
+    #[test]
+    fn century_histogram_buckets() {
+        let ev = |year| Event {
+            year,
+            text: String::new(),
+            url: None,
+        };
+        let events = [ev(1969), ev(1901), ev(2000), ev(1900), ev(-44)];
+        let mono = ThemeName::Mono.theme();
+        let labels: Vec<String> = century_histogram(&events, &mono)
+            .lines()
+            .map(|line| {
+                let count = line.split_whitespace().last().unwrap();
+                format!("{} {count}", line[..8].trim())
+            })
+            .collect();
+        assert_eq!(labels, ["1st BC 1", "19th 1", "20th 3"]);
+
+        // The grouped view buckets the same way
+        let groups: Vec<(String, usize)> = century_groups(&events, false)
+            .into_iter()
+            .map(|(label, events)| (label, events.len()))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                ("1st century BC".to_string(), 1),
+                ("19th century".to_string(), 1),
+                ("20th century".to_string(), 3),
+            ],
+        );
+        assert_eq!(ordinal(12), "12th");
+        assert_eq!(ordinal(22), "22nd");
+    }
+
+    #[test]
+    fn century_of_boundaries() {
+        for (year, century) in [
+            (1900, 19),
+            (1901, 20),
+            (2000, 20),
+            (2001, 21),
+            (1, 1),
+            (0, -1),
+            (-1, -1),
+            (-100, -1),
+            (-101, -2),
+        ] {
+            assert_eq!(century_of(year), century, "{year}");
+        }
+    }
+
+    #[test]
+    fn header_day_follows_language_order() {
+        let date = NaiveDate::from_ymd_opt(2024, 7, 4).unwrap();
+        assert_eq!(header_day(date, "en"), "July 4");
+        assert_eq!(header_day(date, "de"), "4. Juli");
+        assert_eq!(header_day(date, "fr"), "4 juillet");
+        assert_eq!(header_day(date, "es"), "4 de julio");
+        assert_eq!(header_day(date, "xx"), "July 4");
+    }
+
+    #[test]
+    fn year_filters() {
+        let cli = Cli::parse_from(["time-cli", "history", "--last-century"]);
+        let Some(Command::History(args)) = cli.command else { unreachable!() };
+        assert_eq!(args.year_bounds(2024), (Some(1924), None));
+
+        let cli = Cli::parse_from([
+            "time-cli",
+            "history",
+            "--year-from",
+            "-500",
+            "--year-to",
+            "1492",
+        ]);
+        let Some(Command::History(args)) = cli.command else { unreachable!() };
+        let (from, to) = args.year_bounds(2024);
+        assert_eq!((from, to), (Some(-500), Some(1492)));
+
+        let mut response = sample_response();
+        response.retain_years(from, to);
+        let years: Vec<i32> =
+            response.events.iter().map(|ev| ev.year).collect();
+        assert_eq!(years, [1492]);
+        assert_eq!(response.holidays.len(), 2);
+
+        assert!(
+            Cli::try_parse_from([
+                "time-cli",
+                "history",
+                "--last-decade",
+                "--year-from",
+                "1900",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn fetch_progress_counts_the_requested_days() {
+        let bar = fetch_progress(7, "Fetching".to_string()).unwrap();
+        assert_eq!(bar.length(), Some(7));
+        bar.finish_and_clear();
+
+        let spinner = fetch_progress(1, "Fetching".to_string()).unwrap();
+        assert_eq!(spinner.length(), None);
+        spinner.finish_and_clear();
+    }
+
+    #[test]
+    fn interrupt_cleanup_finishes_the_spinner() {
+        let pb = ProgressBar::with_draw_target(
+            None,
+            indicatif::ProgressDrawTarget::hidden(),
+        );
+        pb.enable_steady_tick(Duration::from_millis(10));
+        assert!(!pb.is_finished());
+
+        clean_up_after_interrupt(Some(&pb));
+        assert!(pb.is_finished());
+        clean_up_after_interrupt(None); // nothing running: still fine
+    }
+
+    #[test]
+    fn parse_date_range_bounds() {
+        let range = parse_date_range("2024-02-28..2024-03-01").unwrap();
+        let days: Vec<_> = range.days().map(|d| d.to_string()).collect();
+        assert_eq!(days, ["2024-02-28", "2024-02-29", "2024-03-01"]);
+
+        assert!(parse_date_range("2024-03-02..2024-03-01").is_err());
+        assert!(parse_date_range("2024-01-01..2025-06-01").is_err());
+        assert!(parse_date_range("2024-03-01").is_err());
+    }
+
+    #[test]
+    fn requested_days_dedupes_in_order() {
+        let cli = Cli::parse_from([
+            "time-cli",
+            "history",
+            "--date",
+            "2024-03-02",
+            "--range",
+            "2024-03-01..2024-03-03",
+        ]);
+        let Some(Command::History(args)) = cli.command else { unreachable!() };
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let days: Vec<_> = requested_days(&args, today, None)
+            .unwrap()
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(days, ["2024-03-02", "2024-03-01", "2024-03-03"]);
+    }
+
+    #[test]
+    fn requested_days_week_rolls_over_the_year() {
+        let cli = Cli::parse_from(["time-cli", "history", "--week"]);
+        let Some(Command::History(args)) = cli.command else { unreachable!() };
+        let today = NaiveDate::from_ymd_opt(2024, 12, 29).unwrap();
+        let days: Vec<_> = requested_days(&args, today, None)
+            .unwrap()
+            .iter()
+            .map(|d| d.format("%m-%d").to_string())
+            .collect();
+        assert_eq!(
+            days,
+            ["12-29", "12-30", "12-31", "01-01", "01-02", "01-03", "01-04"],
+        );
+    }
+
+    #[test]
+    fn requested_days_feb_29_uses_leap_year() {
+        let cli =
+            Cli::parse_from(["time-cli", "history", "-m", "2", "-d", "29"]);
+        let Some(Command::History(args)) = cli.command else { unreachable!() };
+        let today = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        let days = requested_days(&args, today, None).unwrap();
+        assert_eq!(days, [NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()]);
+    }
+
+    #[test]
+    fn text_wrap_width_narrow_terminal() {
+        let width = table_width(Some(40)); // clamped up to the minimum
+        assert_eq!(text_wrap_width(width, 15, Some(100)), 35);
+    }
+
+    #[test]
+    fn text_wrap_width_normal_terminal() {
+        assert_eq!(text_wrap_width(120, 15, None), 105);
+        assert_eq!(text_wrap_width(120, 15, Some(100)), 100);
+    }
+
+    #[test]
+    fn text_wrap_width_crowded_table() {
+        // Borders, number, year, age and date columns leave nothing
+        assert_eq!(text_wrap_width(50, 53, None), 20);
+        assert_eq!(text_wrap_width(50, 53, Some(10)), 10);
+    }
+
+    #[test]
+    fn text_wrap_width_ultra_wide_terminal() {
+        assert_eq!(text_wrap_width(400, 15, None), 385);
+        assert_eq!(text_wrap_width(400, 15, Some(100)), 100);
+        assert_eq!(text_wrap_width(400, 5, Some(80)), 80);
+    }
+
+    #[test]
+    fn truncate_to_width_keeps_whole_characters() {
+        let text = "Apollo 11 lands.";
+        assert_eq!(truncate_to_width(text, 16), text);
+        assert_eq!(truncate_to_width(text, 10), "Apollo 11…");
+        assert_eq!(truncate_to_width("two\nlines", 20), "two lines");
+        // 2 columns a character: 3 fit beside the ellipsis in 8, not 4
+        assert_eq!(truncate_to_width("東京都の天気", 8), "東京都…");
+        assert_eq!(truncate_to_width("東京都の天気", 7), "東京都…");
+    }
+
+    #[test]
+    fn wrapping_measures_full_width_text_in_columns() {
+        // 40 double-width characters = 80 display columns, no spaces
+        let text: String = "東京都の天気は晴れです。".repeat(4)[..120].into();
+        let width = text_wrap_width(table_width(Some(60)), 25, None);
+
+        let wrapped = fill(&text, width);
+        assert!(wrapped.lines().count() >= 3, "{wrapped}");
+        for line in wrapped.lines() {
+            let columns = textwrap::core::display_width(line);
+            assert!(columns <= width, "{columns} > {width}: {line}");
+        }
+    }
+
+    #[test]
+    fn clipboard_text_drops_colours_and_links() {
+        let link = hyperlink("Apollo 11", "https://en.wikipedia.org/");
+        let out = format!("| {} | {link} |\n", 1969.yellow().bold());
+        assert_eq!(
+            clipboard_text(Some("Header\n\n"), &out),
+            "Header\n\n| 1969 | Apollo 11 |\n",
+        );
+        assert_eq!(clipboard_text(None, "plain\x1b]8;;x\x07"), "plain");
+    }
+
+    #[test]
+    fn too_tall_counts_the_header_already_printed() {
+        let header = "— On This Day: July 20\n\n";
+        let out = "row 1\nrow 2\nrow 3\n";
+        assert!(!too_tall(None, out, 4));
+        assert!(too_tall(Some(header), out, 4));
+        assert!(!too_tall(Some(header), out, 6));
+    }
+
+    #[test]
+    fn hyperlink_wraps_each_line() {
+        let linked = hyperlink("one\ntwo", "https://x.test");
+        assert_eq!(
+            linked,
+            "\x1b]8;;https://x.test\x1b\\one\x1b]8;;\x1b\\\n\
+             \x1b]8;;https://x.test\x1b\\two\x1b]8;;\x1b\\",
+        );
+    }
+
+    #[test]
+    fn age_at_death_from_entry_text() {
+        let ev = |year, text: &str| Event {
+            year,
+            text: text.into(),
+            url: None,
+        };
+        assert_eq!(
+            age_at_death(&ev(2019, "Agnès Varda, French director (b. 1928)")),
+            Some(91),
+        );
+        assert_eq!(age_at_death(&ev(1900, "Someone (born 1850)")), Some(50));
+        assert_eq!(age_at_death(&ev(44, "Caesar (b. 100 BC)")), None);
+        assert_eq!(age_at_death(&ev(2000, "No birth year given")), None);
+        assert_eq!(age_at_death(&ev(2000, "Typo (b. 2010)")), None);
+    }
+
+    fn shifted(date: &str, duration: &str, backwards: bool) -> String {
+        let moment = parse_moment(date).unwrap();
+        let duration = parse_human_duration(duration).unwrap();
+        shift(moment.at, duration, backwards)
+            .unwrap()
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+
+    #[test]
+    fn parse_human_duration_units() {
+        let parsed = parse_human_duration("1y 2mo, 3 weeks 1d12h30min5s");
+        assert_eq!(
+            parsed.unwrap(),
+            HumanDuration {
+                months: 14,
+                days: 22,
+                seconds: 12 * 3600 + 30 * 60 + 5,
+            },
+        );
+        assert!(parse_human_duration("").is_err());
+        assert!(parse_human_duration("3").is_err());
+        assert!(parse_human_duration("3 fortnights").is_err());
+        assert!(parse_human_duration("-1d").is_err());
+    }
+
+    #[test]
+    fn shift_clamps_to_month_end() {
+        assert_eq!(shifted("2024-01-31", "1mo", false), "2024-02-29 00:00");
+        assert_eq!(shifted("2025-01-31", "1mo", false), "2025-02-28 00:00");
+        assert_eq!(shifted("2024-03-31", "1mo", true), "2024-02-29 00:00");
+    }
+
+    #[test]
+    fn shift_leap_day_by_years() {
+        assert_eq!(shifted("2024-02-29", "1y", false), "2025-02-28 00:00");
+        assert_eq!(shifted("2024-02-29", "4y", true), "2020-02-29 00:00");
+        assert_eq!(shifted("2024-02-28", "1d", false), "2024-02-29 00:00");
+    }
+
+    #[test]
+    fn shift_sub_day_parts() {
+        assert_eq!(
+            shifted("2024-12-31T23:30:00+01:00", "1h", false),
+            "2025-01-01 00:30",
+        );
+    }
+
+    #[test]
+    fn validate_format_accepts_known_specifiers() {
+        assert!(validate_format("%Y-%m-%d %H:%M:%S").is_ok());
+        assert!(validate_format("%-d %B, %:z %.3f 100%%").is_ok());
+        assert!(validate_format("no specifiers at all").is_ok());
+    }
+
+    #[test]
+    fn validate_format_names_bad_token() {
+        let err = validate_format("%Y-%Q-%d").unwrap_err().to_string();
+        assert!(err.contains("'%Q'"), "{err}");
+        let err = validate_format("%H:%M %").unwrap_err().to_string();
+        assert!(err.contains("'%'"), "{err}");
+    }
+
+    #[test]
+    fn relative_time_buckets() {
+        assert_eq!(relative_time(0), "just now");
+        assert_eq!(relative_time(-1), "1 second ago");
+        assert_eq!(relative_time(-3 * 3600 - 59), "3 hours ago");
+        assert_eq!(relative_time(2 * 86_400), "in 2 days");
+        assert_eq!(relative_time(-15 * 86_400), "2 weeks ago");
+        assert_eq!(relative_time(-45 * 86_400), "1 month ago");
+        assert_eq!(relative_time(-366 * 86_400), "1 year ago");
+        assert_eq!(relative_time(3 * 365 * 86_400 + 5), "in 3 years");
+    }
+
+    #[test]
+    fn days_until_rolls_over_and_handles_leap_days() {
+        let day = |s| parse_month_day(s).unwrap();
+        let today = parse_iso_date("2025-06-15").unwrap();
+        // later this year
+        assert_eq!(days_until(today, day("12-25"), LeapDay::Feb28), 193);
+        assert_eq!(days_until(today, day("06-15"), LeapDay::Feb28), 0);
+        // already passed: next year
+        assert_eq!(days_until(today, day("03-01"), LeapDay::Feb28), 259);
+        assert_eq!(days_until(today, day("06-14"), LeapDay::Feb28), 364);
+
+        // 2026 has no Feb 29
+        let leap = day("02-29");
+        assert_eq!(days_until(today, leap, LeapDay::Feb28), 258);
+        assert_eq!(days_until(today, leap, LeapDay::Mar1), 259);
+        let before_leap_day = parse_iso_date("2028-02-01").unwrap();
+        assert_eq!(days_until(before_leap_day, leap, LeapDay::Mar1), 28);
+    }
+
+    #[test]
+    fn parse_instant_accepts_unix_seconds() {
+        let moment = parse_instant("1700000000").unwrap();
+        assert_eq!(moment.at.to_rfc3339(), "2023-11-14T22:13:20+00:00");
+        assert!(parse_instant("2024-03-01").unwrap().date_only);
+        assert!(parse_instant("soon").is_err());
+    }
+
+    #[test]
+    fn default_theme_colours_each_category_apart() {
+        let sections = [
+            EventType::Events,
+            EventType::Births,
+            EventType::Deaths,
+            EventType::Holidays,
+        ];
+        let accents = ThemeName::Default.theme().accents;
+        let colours: HashSet<String> = sections
+            .iter()
+            .map(|section| format!("{:?}", accents.of(*section).unwrap()))
+            .collect();
+        assert_eq!(colours.len(), sections.len());
+
+        let mono = ThemeName::Mono.theme().accents;
+        assert!(sections.iter().all(|section| mono.of(*section).is_none()));
+    }
+
+    #[test]
+    fn parse_month_accepts_numbers_and_names() {
+        for input in ["7", "07", "July", "jul", "JULY", "Jul"] {
+            assert_eq!(parse_month(input), Ok(7), "{input}");
+        }
+        assert_eq!(parse_month("12"), Ok(12));
+        for input in ["0", "13", "Juli", "ju", "", "-1"] {
+            assert!(parse_month(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_separator_escapes() {
+        assert_eq!(parse_separator(r"\t").unwrap(), "\t");
+        assert_eq!(parse_separator(r"\0").unwrap(), "\0");
+        assert_eq!(parse_separator(r" | ").unwrap(), " | ");
+        assert_eq!(parse_separator(r"a\\b\n").unwrap(), "a\\b\n");
+        assert!(parse_separator(r"\x").is_err());
+        assert!(parse_separator("").is_err());
+    }
+
+    #[test]
+    fn parse_locale_picks_the_decimal_mark() {
+        for (input, decimal) in [
+            ("de", ','),
+            ("de_DE.UTF-8", ','),
+            ("pt-BR", ','),
+            ("en_US", '.'),
+            ("ja", '.'),
+        ] {
+            assert_eq!(parse_locale(input).unwrap().decimal, decimal);
+        }
+        assert!(parse_locale("").is_err());
+        assert!(parse_locale("12").is_err());
+        assert_eq!(
+            NumberLocale { decimal: ',' }.number(" 16.7".to_string()),
+            " 16,7",
+        );
+    }
+
+    #[test]
+    fn weekday_context_for_gregorian_dates_only() {
+        assert_eq!(weekday_context(1969, 7, 20), "1969-07-20 (Sunday)");
+        assert_eq!(weekday_context(1600, 2, 29), "1600-02-29 (Tuesday)");
+        assert_eq!(weekday_context(1582, 10, 4), "—");
+        assert_eq!(weekday_context(1900, 2, 29), "—");
     }
 
     #[test]
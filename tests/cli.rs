@@ -18,6 +18,266 @@ fn test_statistics_arg() {
         .stdout(predicate::str::contains("Time statistics"));
 }
 
+#[test]
+fn test_statistics_json() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["--statistics", "--json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stats: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+    for field in ["day_progress", "year_progress"] {
+        let value = stats[field].as_f64().unwrap();
+        assert!((0.0..=100.0).contains(&value), "{field} = {value}");
+    }
+    assert!(stats["date"].is_string());
+    assert!(stats["unix_timestamp"].is_i64());
+}
+
+#[test]
+fn test_now_subcommand() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.arg("now");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("The current time is:"));
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["now", "--timezone", "Asia/Kolkata", "--time-format", "%:z"]);
+    cmd.assert().success().stdout("+05:30\n");
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["now", "--json", "--utc"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let now: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(now["utc_offset"], "+00:00");
+    assert!(now["date"].as_str().unwrap().ends_with("+00:00"));
+    assert!(now["unix_timestamp"].is_i64());
+}
+
+#[test]
+fn test_countdown() {
+    let countdown = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("time_cli").unwrap();
+        cmd.env("TIME_CLI_NOW", "2025-06-15T12:00:00Z")
+            .args(["--utc", "countdown"])
+            .args(args);
+        cmd
+    };
+
+    countdown(&["12-25"])
+        .assert()
+        .success()
+        .stdout("193 days until 12-25\n");
+    countdown(&["06-14"])
+        .assert()
+        .success()
+        .stdout("364 days until 06-14\n");
+    countdown(&["02-29", "--leap-day", "mar1"])
+        .assert()
+        .success()
+        .stdout("259 days until 02-29\n");
+    countdown(&["13-01"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected MM-DD"));
+}
+
+#[test]
+fn test_schema_matches_json_output() {
+    let schema = |output: &str| {
+        let mut cmd = Command::cargo_bin("time_cli").unwrap();
+        cmd.args(["schema", output]);
+        let output = cmd.assert().success().get_output().stdout.clone();
+        serde_json::from_slice::<serde_json::Value>(&output).unwrap()
+    };
+
+    let stats_schema = schema("statistics");
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["--statistics", "--json"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stats: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    for key in stats.as_object().unwrap().keys() {
+        assert!(
+            stats_schema["properties"].get(key).is_some(),
+            "{key} missing from {stats_schema:#}",
+        );
+    }
+
+    let history_schema = schema("history");
+    let event = &history_schema["$defs"]["Event"];
+    assert_eq!(event["required"], serde_json::json!(["year", "text"]));
+    assert!(event["properties"]["url"].is_object());
+    assert!(history_schema["$defs"]["Holiday"].is_object());
+}
+
+#[test]
+fn test_statistics_prometheus() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["--statistics", "--prometheus"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let text = String::from_utf8(output).unwrap();
+
+    let mut samples = 0;
+    for line in text.lines() {
+        if let Some(comment) = line.strip_prefix("# ") {
+            let mut parts = comment.splitn(3, ' ');
+            assert!(matches!(parts.next(), Some("HELP" | "TYPE")), "{line}");
+            assert!(parts.next().unwrap().starts_with("time_cli_"));
+            assert!(parts.next().is_some(), "{line}");
+            continue;
+        }
+        let (name, value) = line.split_once(' ').expect(line);
+        assert!(
+            name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+            "bad metric name in {line:?}",
+        );
+        assert!(text.contains(&format!("# TYPE {name} gauge")), "{name}");
+        value.parse::<f64>().expect(line);
+        samples += 1;
+    }
+    assert!(samples >= 3);
+    assert!(text.contains("\ntime_cli_day_progress "));
+    assert!(text.contains("\ntime_cli_year_progress "));
+    assert!(text.contains("\ntime_cli_unix_timestamp "));
+}
+
+#[test]
+fn test_statistics_explain() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.arg("--statistics");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("ISO 8601 week").not());
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["--statistics", "--explain"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("ISO 8601 week number"))
+        .stdout(predicate::str::contains("seconds since 1970-01-01"))
+        .stdout(predicate::str::contains("in your local time zone"));
+
+    // The notes name the zone actually in use
+    for (zone, name) in [
+        (&["--utc"][..], "in UTC)"),
+        (&["--timezone", "Europe/Berlin"], "in Europe/Berlin)"),
+    ] {
+        let mut cmd = Command::cargo_bin("time_cli").unwrap();
+        cmd.args(["--statistics", "--explain"]).args(zone);
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains(format!("today's date {name}")))
+            .stdout(predicate::str::contains(format!("wall-clock time {name}")))
+            .stdout(predicate::str::contains("local time zone").not());
+    }
+}
+
+#[test]
+fn test_statistics_daylight() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.arg("--statistics");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Daylight").not());
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["--statistics", "--lat", "-33.87", "--lon", "151.21"]);
+    cmd.assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                r"Daylight +: \d\d:\d\d–\d\d:\d\d \(\d+h \d+m\)",
+            )
+            .unwrap(),
+        );
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["--statistics", "--lat", "91", "--lon", "0"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("not a number of degrees"));
+}
+
+#[test]
+fn test_progress_precision() {
+    let stats = |precision: &str| {
+        let mut cmd = Command::cargo_bin("time_cli").unwrap();
+        cmd.env("TIME_CLI_NOW", "2024-03-01T12:00:00Z")
+            .args(["--statistics", "--utc"])
+            .args(["--progress-precision", precision]);
+        cmd
+    };
+
+    stats("0")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("  50 %"));
+    stats("2")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("  50.00 %"));
+    stats("3")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not in 0..=2"));
+    stats("0")
+        .args(["--compact", "--locale", "de"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Day 61/366 50% | Week 9 | Year 17%"));
+    stats("2")
+        .args(["--compact", "--locale", "de"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("50,00% | Week 9 | Year 16,67%"));
+}
+
+#[test]
+fn test_statistics_remaining_time() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.env("TIME_CLI_NOW", "2024-03-01T18:48:00Z")
+        .args(["--statistics", "--utc"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Remaining today : 5h 12m\n"))
+        .stdout(predicate::str::contains(
+            "Remaining year  : 305 days (43 weeks)\n",
+        ));
+}
+
+#[test]
+fn test_locale_decimal_mark() {
+    let stats = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("time_cli").unwrap();
+        cmd.env("TIME_CLI_NOW", "2024-03-01T04:00:00Z")
+            .args(["--statistics", "--utc"])
+            .args(args);
+        cmd
+    };
+
+    stats(&["--locale", "de_DE"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("16,7 %"))
+        .stdout(predicate::str::contains("16.7").not());
+    stats(&["--locale", "de", "--compact"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Day 61/366 16,7%"));
+    stats(&["--locale", "en_US"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("16.7 %"));
+    stats(&["--locale", "de", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(",7").not());
+    stats(&["--locale", "42"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a locale"));
+}
+
 #[test]
 fn test_invalid_date() {
     let mut cmd = Command::cargo_bin("time_cli").unwrap();
@@ -25,4 +285,2706 @@ fn test_invalid_date() {
     cmd.assert().failure().stderr(predicate::str::contains(
         "'04-31' is not a valid calendar date",
     ));
-}
\ No newline at end of file
+}
+
+/// A mock Wikipedia REST API plus a throw-away response cache, so runs
+/// never touch the network or the user's real cache directory.
+struct MockWiki {
+    server: mockito::ServerGuard,
+    cache_dir: tempfile::TempDir,
+}
+
+impl MockWiki {
+    fn new() -> Self {
+        Self {
+            server: mockito::Server::new(),
+            cache_dir: tempfile::tempdir().unwrap(),
+        }
+    }
+
+    /// Serve a canned “On This Day” feed.
+    fn feed(
+        &mut self,
+        event_type: &str,
+        month: u32,
+        day: u32,
+        body: &str,
+    ) -> mockito::Mock {
+        self.feed_in("", event_type, month, day, body)
+    }
+
+    /// Serve a canned feed under `/<lang>`, for runs that point
+    /// `TEST_WIKIPEDIA_API_URL` at [`Self::per_lang_url`].
+    fn feed_in(
+        &mut self,
+        lang: &str,
+        event_type: &str,
+        month: u32,
+        day: u32,
+        body: &str,
+    ) -> mockito::Mock {
+        let prefix = if lang.is_empty() {
+            String::new()
+        } else {
+            format!("/{lang}")
+        };
+        let path = format!(
+            "{prefix}/api/rest_v1/feed/onthisday/{event_type}/{month}/{day}",
+        );
+        self.server
+            .mock("GET", path.as_str())
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create()
+    }
+
+    /// Serve a canned featured-content feed for `date` (`YYYY/MM/DD`).
+    fn featured(&mut self, date: &str, body: &str) -> mockito::Mock {
+        let path = format!("/api/rest_v1/feed/featured/{date}");
+        self.server
+            .mock("GET", path.as_str())
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create()
+    }
+
+    /// API base URL with a `{lang}` placeholder, routing each language to
+    /// the mocks made by [`Self::feed_in`].
+    fn per_lang_url(&self) -> String {
+        format!("{}/{{lang}}", self.server.url())
+    }
+
+    /// `time_cli` wired to this mock.
+    fn cmd(&self) -> Command {
+        let mut cmd = Command::cargo_bin("time_cli").unwrap();
+        cmd.env("TEST_WIKIPEDIA_API_URL", self.server.url())
+            .env("TIME_CLI_CACHE_DIR", self.cache_dir.path())
+            .env_remove("NO_COLOR");
+        cmd
+    }
+}
+
+#[test]
+fn test_columns_event_only() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands on the Moon."}]}"#,
+    );
+
+    let mut cmd = wiki.cmd();
+    cmd.args([
+        "history", "--quiet", "-m", "7", "-d", "20", "--columns", "event",
+    ]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    let row = stdout
+        .lines()
+        .find(|line| line.contains("Apollo 11"))
+        .expect("event row missing");
+    // A single-column row only has the left and right borders
+    assert_eq!(row.matches('│').count(), 2, "row: {row}");
+    assert!(!stdout.contains("1969"));
+}
+
+#[test]
+fn test_columns_event_only_wraps_at_the_full_width() {
+    let mut wiki = MockWiki::new();
+    // 42 characters: too long beside a year column at -w 50, not alone
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[
+            {"year":1969,"text":"Apollo 11 lands; two men walk on the Moon."}
+        ]}"#,
+    );
+    let rows = |columns: &str| {
+        let output = wiki
+            .cmd()
+            .args(["history", "--silent", "--theme", "mono", "-w", "50"])
+            .args(["-m", "7", "-d", "20", "--columns", columns])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let stdout = String::from_utf8(output).unwrap();
+        stdout.lines().filter(|line| line.starts_with('│')).count()
+    };
+
+    // Header plus the entry on one line, or wrapped onto two
+    assert_eq!(rows("event"), 2);
+    assert_eq!(rows("year,event"), 3);
+}
+
+#[test]
+fn test_tiny_width_still_reads() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "all",
+        7,
+        20,
+        r#"{"events":[
+            {"year":1969,"text":"Apollo 11 lands; two men walk on the Moon."}
+        ],
+        "holidays":[{"text":"International Chess Day"}]}"#,
+    );
+    let run = |flags: &[&str]| {
+        let output = wiki
+            .cmd()
+            .args(["history", "--silent", "--theme", "mono", "-m", "7"])
+            .args(["-d", "20", "-t", "events,holidays"])
+            .args(flags)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    for width in ["1", "10", "49"] {
+        let stdout = run(&["-w", width, "--numbered"]);
+        for line in stdout.lines() {
+            assert!(line.chars().count() <= 50, "-w {width}: {line}");
+        }
+        assert!(stdout.contains("Apollo 11 lands;"), "-w {width}:\n{stdout}");
+        assert!(stdout.contains("Chess Day"), "-w {width}:\n{stdout}");
+
+        let stdout = run(&["-w", width, "--truncate"]);
+        let apollo = stdout.lines().find(|line| line.contains("Apollo"));
+        assert!(apollo.unwrap().contains('…'), "-w {width}:\n{stdout}");
+    }
+}
+
+#[test]
+fn test_columns_unknown_name() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["history", "--columns", "year,bogus"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'bogus'"));
+}
+
+#[test]
+fn test_columns_text_alias_and_holidays() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands on the Moon."}]}"#,
+    );
+    wiki.cmd()
+        .args(["history", "--quiet", "--columns", "text,year"])
+        .args(["-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "│ Apollo 11 lands on the Moon. ┆ 1969 │",
+        ));
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["history", "-t", "holidays", "--columns", "year"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("holidays have no year"));
+}
+
+#[test]
+fn test_completions_bash() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["completions", "bash"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("history"))
+        .stdout(predicate::str::contains("births"));
+}
+
+#[test]
+fn test_man_stdout() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.arg("man");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(".TH time-cli 1"))
+        .stdout(predicate::str::contains(".TH time-cli-history 1"));
+}
+
+#[test]
+fn test_width_override() {
+    let long_text = "The quick brown fox jumps over the lazy dog. ".repeat(6);
+    let body =
+        format!(r#"{{"events":[{{"year":1901,"text":"{long_text}"}}]}}"#);
+    let mut wiki = MockWiki::new();
+    wiki.feed("events", 3, 1, &body);
+
+    let mut cmd = wiki.cmd();
+    cmd.args([
+        "history", "--quiet", "-m", "3", "-d", "1", "--width", "60",
+    ]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    let table_lines: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with(['┌', '│', '├', '╞', '└']))
+        .collect();
+    assert!(table_lines.len() > 5, "text was not wrapped:\n{stdout}");
+    for line in table_lines {
+        assert!(line.chars().count() <= 60, "line too wide: {line}");
+    }
+}
+
+#[test]
+fn test_rss_feed() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[
+            {"year":1969,"text":"Apollo 11 lands on the Moon."},
+            {"year":1944,"text":"A plot to assassinate Hitler fails."}
+        ]}"#,
+    );
+
+    let mut cmd = wiki.cmd();
+    cmd.args([
+        "history", "--quiet", "-m", "7", "-d", "20", "--feed", "rss",
+    ]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+
+    let channel = rss::Channel::read_from(&output[..]).expect("invalid RSS");
+    assert_eq!(channel.title(), "On This Day: July 20 (events)");
+    assert_eq!(channel.items().len(), 2);
+    assert_eq!(channel.items()[0].title(), Some("1944"));
+}
+
+#[test]
+fn test_atom_feed() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "holidays",
+        7,
+        20,
+        r#"{"holidays":[{"text":"Moon Day"}]}"#,
+    );
+
+    let mut cmd = wiki.cmd();
+    cmd.args([
+        "history", "--quiet", "-m", "7", "-d", "20", "-t", "holidays",
+        "--feed", "atom",
+    ]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+
+    let feed =
+        atom_syndication::Feed::read_from(&output[..]).expect("invalid Atom");
+    assert_eq!(feed.entries().len(), 1);
+    assert_eq!(feed.entries()[0].title().as_str(), "Moon Day");
+
+    // TIME_CLI_NOW pins `updated`, so the feed is reproducible
+    let pinned = || {
+        let mut cmd = wiki.cmd();
+        cmd.env("TIME_CLI_NOW", "2024-07-20T20:17:00Z").args([
+            "--utc", "history", "--quiet", "-m", "7", "-d", "20", "-t",
+            "holidays", "--feed", "atom",
+        ]);
+        cmd.assert().success().get_output().stdout.clone()
+    };
+    let output = pinned();
+    assert_eq!(output, pinned());
+    let feed = atom_syndication::Feed::read_from(&output[..]).unwrap();
+    assert_eq!(feed.updated().to_rfc3339(), "2024-07-20T20:17:00+00:00");
+    let entry = &feed.entries()[0];
+    assert_eq!(entry.updated().to_rfc3339(), "2024-07-20T20:17:00+00:00");
+}
+
+#[test]
+fn test_disk_cache_reused_across_runs() {
+    let mut wiki = MockWiki::new();
+    let mock = wiki
+        .feed("births", 1, 2, r#"{"births":[{"year":1920,"text":"Someone"}]}"#)
+        .expect(1);
+
+    for _ in 0..2 {
+        wiki.cmd()
+            .args(["history", "--quiet", "-t", "births", "-m", "1", "-d", "2"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Someone"));
+    }
+    mock.assert();
+}
+
+#[test]
+fn test_show_cache_status() {
+    let mut wiki = MockWiki::new();
+    wiki.feed("births", 1, 2, r#"{"births":[{"year":1920,"text":"Someone"}]}"#);
+
+    for status in ["cache: miss ", "cache: hit "] {
+        wiki.cmd()
+            .args(["history", "--show-cache-status", "-t", "births"])
+            .args(["-m", "1", "-d", "2"])
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(status));
+    }
+}
+
+#[test]
+fn test_range_prints_one_section_per_day() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        3,
+        1,
+        r#"{"events":[{"year":1901,"text":"First of March A"},
+                      {"year":1902,"text":"First of March B"}]}"#,
+    );
+    wiki.feed(
+        "events",
+        3,
+        2,
+        r#"{"events":[{"year":1903,"text":"Second of March"}]}"#,
+    );
+
+    let output = wiki
+        .cmd()
+        .args(["history", "--quiet", "--range", "2024-03-01..2024-03-02"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert_eq!(stdout.matches("On This Day").count(), 2);
+    assert!(stdout.contains("March 1") && stdout.contains("March 2"));
+    assert_eq!(stdout.matches("of March").count(), 3);
+}
+
+#[test]
+fn test_week_shows_seven_days_from_today() {
+    let mut wiki = MockWiki::new();
+    let days = [(2, 26), (2, 27), (2, 28), (2, 29), (3, 1), (3, 2), (3, 3)];
+    for (month, day) in days {
+        wiki.feed(
+            "births",
+            month,
+            day,
+            &format!(
+                r#"{{"births":[{{"year":1900,"text":"Born {month}/{day}"}}]}}"#
+            ),
+        );
+    }
+
+    let output = wiki
+        .cmd()
+        .env("TIME_CLI_NOW", "2024-02-26T12:00:00Z")
+        .args(["--utc", "history", "--quiet", "-t", "births", "--week"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert_eq!(stdout.matches("On This Day").count(), 7);
+    let positions: Vec<usize> = days
+        .iter()
+        .map(|(month, day)| format!("Born {month}/{day}"))
+        .map(|text| stdout.find(&text).unwrap())
+        .collect();
+    assert!(positions.is_sorted(), "{stdout}");
+}
+
+#[test]
+fn test_dates_json_keyed_by_iso_date() {
+    let mut wiki = MockWiki::new();
+    wiki.feed("holidays", 12, 25, r#"{"holidays":[{"text":"Christmas"}]}"#);
+
+    let output = wiki
+        .cmd()
+        .args([
+            "history", "--quiet", "-t", "holidays", "--json", "--date",
+            "2023-12-25", "--date", "2024-12-25", "--date", "2023-12-25",
+        ])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let days = json.as_object().unwrap();
+    assert_eq!(days.len(), 2);
+    assert_eq!(json["2024-12-25"][0]["text"], "Christmas");
+}
+
+#[test]
+fn test_json_entry_shapes() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "all",
+        12,
+        25,
+        r#"{"events":[{"year":1991,"text":"Gorbachev resigns."}],
+            "holidays":[{"text":"Christmas"},{"text":"Quaid-e-Azam Day"}]}"#,
+    );
+
+    let output = wiki
+        .cmd()
+        .args(["history", "-t", "all", "--json", "--date", "2024-12-25"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let day = &json["2024-12-25"];
+
+    for holiday in day["holidays"].as_array().unwrap() {
+        let keys: Vec<_> = holiday.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["text"]);
+    }
+    let event = day["events"][0].as_object().unwrap();
+    assert_eq!(event["year"], 1991);
+    assert_eq!(event["text"], "Gorbachev resigns.");
+}
+
+#[test]
+fn test_json_fields_projection() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands on the Moon.",
+            "pages":[{"content_urls":{"desktop":{"page":"https://x.test"}}}]
+        }]}"#,
+    );
+
+    let output = wiki
+        .cmd()
+        .args(["history", "--json", "--fields", "year", "--date", "2024-07-20"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["2024-07-20"][0], serde_json::json!({"year": 1969}));
+
+    wiki.cmd()
+        .args(["history", "--json", "--fields", "year,title"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'title'"))
+        .stderr(predicate::str::contains("year, text, url"));
+}
+
+#[test]
+fn test_show_age_column() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "deaths",
+        3,
+        29,
+        r#"{"deaths":[
+            {"year":2019,"text":"Agnès Varda, French director (b. 1928)"},
+            {"year":1912,"text":"Robert Falcon Scott, explorer"}
+        ]}"#,
+    );
+
+    let output = wiki
+        .cmd()
+        .args([
+            "history", "--quiet", "-t", "deaths", "-m", "3", "-d", "29",
+            "--show-age",
+        ])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert!(stdout.contains("Age"));
+    let varda = stdout.lines().find(|l| l.contains("Varda")).unwrap();
+    assert!(varda.contains(" 91 "), "row: {varda}");
+}
+
+#[test]
+fn test_show_age_requires_deaths() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["history", "--show-age"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("only applies to --type deaths"));
+}
+
+#[test]
+fn test_context_in_a_narrow_table() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "deaths",
+        3,
+        29,
+        r#"{"deaths":[
+            {"year":2019,"text":"Agnès Varda, French director (b. 1928)"},
+            {"year":1912,"text":"Robert Falcon Scott, explorer"}
+        ]}"#,
+    );
+
+    let output = wiki
+        .cmd()
+        .args(["history", "--silent", "--theme", "mono", "-w", "50"])
+        .args(["-t", "deaths", "-m", "3", "-d", "29"])
+        .args(["--numbered", "--show-age", "--context"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    // The other columns leave the text room for whole words
+    assert!(stdout.contains("2019-03-29"), "{stdout}");
+    assert!(stdout.contains("Agnès Varda,"), "{stdout}");
+    assert!(stdout.contains("Robert Falcon"), "{stdout}");
+    let rows = stdout.lines().filter(|line| line.starts_with('│')).count();
+    assert!(rows <= 8, "{rows} lines:\n{stdout}");
+}
+
+#[test]
+fn test_add_and_sub() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["add", "2024-01-31", "1 month"]);
+    cmd.assert().success().stdout("2024-02-29\n");
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["sub", "2024-03-01", "1d"]);
+    cmd.assert().success().stdout("2024-02-29\n");
+}
+
+#[test]
+fn test_type_all_shows_every_section() {
+    let mut wiki = MockWiki::new();
+    let mock = wiki.feed(
+        "all",
+        5,
+        4,
+        r#"{
+            "events": [{"year":1970,"text":"Kent State shootings"}],
+            "births": [{"year":1929,"text":"Audrey Hepburn"}],
+            "deaths": [{"year":1980,"text":"Josip Broz Tito (b. 1892)"}],
+            "holidays": [{"text":"Star Wars Day"}]
+        }"#,
+    );
+
+    let output = wiki
+        .cmd()
+        .args(["history", "--quiet", "-t", "all", "-m", "5", "-d", "4"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    mock.assert();
+    for title in ["Events", "Births", "Deaths", "Holidays"] {
+        assert!(
+            stdout.lines().any(|l| l.contains(title) && !l.contains('│')),
+            "missing section {title}:\n{stdout}",
+        );
+    }
+    for text in ["Kent State", "Hepburn", "Tito", "Star Wars Day"] {
+        assert!(stdout.contains(text));
+    }
+}
+
+#[test]
+fn test_selected_feed() {
+    let mut wiki = MockWiki::new();
+    let mock = wiki.feed(
+        "selected",
+        7,
+        20,
+        r#"{"selected":[
+            {"year":1969,"text":"Apollo 11 lands on the Moon."},
+            {"year":1976,"text":"Viking 1 lands on Mars."}
+        ]}"#,
+    );
+
+    wiki.cmd()
+        .args(["history", "--quiet", "-t", "selected", "-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Apollo 11"))
+        .stdout(predicate::str::contains("Viking 1"))
+        .stdout(predicate::str::contains("1976"))
+        .stdout(predicate::str::contains("No entries").not());
+    mock.assert();
+}
+
+#[test]
+fn test_show_url_prints_each_request() {
+    let mut wiki = MockWiki::new();
+    for day in [20, 21] {
+        wiki.feed("births", 7, day, r#"{"births":[]}"#);
+    }
+    let output = wiki
+        .cmd()
+        .args(["history", "--show-url", "-t", "births"])
+        .args(["--range", "2024-07-20..2024-07-21"])
+        .assert()
+        .success()
+        .get_output()
+        .stderr
+        .clone();
+    let stderr = String::from_utf8(output).unwrap();
+    for day in [20, 21] {
+        let line = format!(
+            "GET {}/api/rest_v1/feed/onthisday/births/7/{day}",
+            wiki.server.url(),
+        );
+        assert!(stderr.lines().any(|l| l == line), "{stderr}");
+    }
+}
+
+#[test]
+fn test_show_url_prints_a_request_that_fails() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    let cache_dir = tempfile::tempdir().unwrap();
+    cmd.env("TEST_WIKIPEDIA_API_URL", "http://127.0.0.1:9")
+        .env("TIME_CLI_CACHE_DIR", cache_dir.path())
+        .args(["history", "--show-url", "-m", "7", "-d", "20"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "GET http://127.0.0.1:9/api/rest_v1/feed/onthisday/events/7/20\n",
+        ))
+        .stderr(predicate::str::contains("Could not reach 127.0.0.1"));
+}
+
+#[test]
+fn test_year_column_right_aligned() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[
+            {"year":1969,"text":"Apollo 11 lands."},
+            {"year":356,"text":"Alexander the Great is born."}
+        ]}"#,
+    );
+    let run = |align: &str| {
+        let output = wiki
+            .cmd()
+            .args(["history", "--silent", "-w", "50", "--align", align])
+            .args(["-m", "7", "-d", "20"])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    let left = run("left");
+    assert!(left.contains("│ 1969 ┆ Apollo 11 lands.             │"), "{left}");
+    assert!(left.contains("│  356 ┆ Alexander the Great is born. │"), "{left}");
+    let right = run("right");
+    assert!(right.contains("│ 1969 ┆             Apollo 11 lands. │"));
+}
+
+#[test]
+fn test_truncate_keeps_one_line_per_entry() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[
+            {"year":1969,
+             "text":"Apollo 11 lands on the Moon and Buzz Aldrin walks."},
+            {"year":1881,"text":"Sitting Bull surrenders."}
+        ]}"#,
+    );
+    let run = |flag: Option<&str>| {
+        let output = wiki
+            .cmd()
+            .env("TIME_CLI_NOW", "2069-07-20T12:00:00Z")
+            .args(["history", "--silent", "--theme", "mono", "-w", "50"])
+            .args(["-m", "7", "-d", "20", "--truncate"])
+            .args(flag)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    let stdout = run(None);
+    let rows: Vec<&str> =
+        stdout.lines().filter(|line| line.contains(" ┆ ")).collect();
+    assert_eq!(rows.len(), 3, "{stdout}"); // header and two entries
+    assert!(rows[1].contains("Sitting Bull surrenders. "), "{stdout}");
+    assert!(rows[2].starts_with("│ 1969 ┆ Apollo 11 lands on"), "{stdout}");
+    assert!(rows[2].ends_with("… │"), "{stdout}");
+    assert!(!stdout.contains("Buzz"), "{stdout}");
+
+    // 1969 is 100 years back: still one line, the marker ahead of the cut
+    let stdout = run(Some("--anniversaries"));
+    let rows: Vec<&str> =
+        stdout.lines().filter(|line| line.contains(" ┆ ")).collect();
+    assert_eq!(rows.len(), 3, "{stdout}");
+    assert_eq!(stdout.lines().count(), 7, "{stdout}"); // plus 4 rules
+    assert!(rows[2].starts_with("│ 1969 ┆ ⭐ 100y Apollo 11"), "{stdout}");
+    assert!(rows[2].ends_with("… │"), "{stdout}");
+}
+
+#[test]
+fn test_anniversaries_mark_round_years() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[
+            {"year":1969,"text":"Apollo 11 lands on the Moon."},
+            {"year":1950,"text":"Not a round anniversary."}
+        ]}"#,
+    );
+    let run = |flags: &[&str]| {
+        let output = wiki
+            .cmd()
+            .env("TIME_CLI_NOW", "2069-07-20T12:00:00Z")
+            .args(["history", "--silent", "--utc"])
+            .args(flags)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    let marked = run(&["--anniversaries"]);
+    assert_eq!(marked.matches("⭐").count(), 1, "{marked}");
+    assert!(marked.contains("⭐ 100 years ago"), "{marked}");
+    assert!(!run(&[]).contains("⭐"));
+
+    // One-line layouts put the marker inline instead of on a second line
+    assert_eq!(
+        run(&["--anniversaries", "--plain", "--separator", ","]),
+        "1950,Not a round anniversary.\n\
+         1969,⭐ 100y Apollo 11 lands on the Moon.\n",
+    );
+    let truncated = run(&["--anniversaries", "--truncate", "--theme", "mono"]);
+    let row = truncated
+        .lines()
+        .find(|line| line.contains("1969"))
+        .unwrap_or_default();
+    assert!(row.contains("┆ ⭐ 100y Apollo 11 lands on the Moon."), "{row}");
+    assert!(!truncated.contains("years ago"), "{truncated}");
+}
+
+#[test]
+fn test_ascii_preset() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands on the Moon."}]}"#,
+    );
+    let output = wiki
+        .cmd()
+        .args(["history", "--silent", "--preset", "ascii"])
+        .args(["-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert!(stdout.contains("+------+"), "{stdout}");
+    assert!(stdout.contains("| 1969 | Apollo 11"), "{stdout}");
+    assert!(!stdout.contains(['┌', '│', '─', '╞']), "{stdout}");
+}
+
+#[test]
+fn test_featured_picture() {
+    let mut wiki = MockWiki::new();
+    wiki.featured(
+        "2024/07/20",
+        r#"{"image":{
+            "title":"File:Aldrin Apollo 11.jpg",
+            "description":{"text":"Buzz Aldrin on the Moon.","lang":"en"},
+            "file_page":"https://commons.wikimedia.org/wiki/File:Aldrin.jpg"
+        }}"#,
+    );
+    let args = ["history", "--type", "featured", "--date", "2024-07-20"];
+
+    wiki.cmd()
+        .args(args)
+        .args(["--silent", "--theme", "mono"])
+        .assert()
+        .success()
+        .stdout(
+            "Picture of the day: Aldrin Apollo 11.jpg\n\
+             Buzz Aldrin on the Moon.\n\
+             Source: https://commons.wikimedia.org/wiki/File:Aldrin.jpg\n",
+        );
+    wiki.cmd()
+        .args(args)
+        .args(["--no-header", "--theme", "mono"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("On This Day").not())
+        .stdout(predicate::str::starts_with(
+            "Picture of the day: Aldrin Apollo 11.jpg\n",
+        ));
+
+    let output = wiki
+        .cmd()
+        .args(args)
+        .arg("--json")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["2024-07-20"]["description"], "Buzz Aldrin on the Moon.");
+
+    // The runs above filled the disk cache
+    wiki.cmd()
+        .args(args)
+        .args(["--offline", "--silent", "--theme", "mono"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "Picture of the day: Aldrin Apollo 11.jpg\n",
+        ));
+
+    wiki.cmd()
+        .args(args)
+        .args(["--plain", "--columns", "year"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--type featured can't be combined with --plain, --columns",
+        ));
+}
+
+#[test]
+fn test_featured_range_reports_failed_days_and_keeps_the_rest() {
+    let mut wiki = MockWiki::new();
+    wiki.featured(
+        "2024/07/20",
+        r#"{"image":{
+            "title":"File:Aldrin Apollo 11.jpg",
+            "file_page":"https://commons.wikimedia.org/wiki/File:Aldrin.jpg"
+        }}"#,
+    );
+    wiki.server
+        .mock("GET", "/api/rest_v1/feed/featured/2024/07/21")
+        .with_status(500)
+        .create();
+
+    let output = wiki
+        .cmd()
+        .args(["history", "--quiet", "--type", "featured"])
+        .args(["--range", "2024-07-20..2024-07-21"])
+        .assert()
+        .failure()
+        .get_output()
+        .clone();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(stdout.contains("Aldrin Apollo 11.jpg"), "{stdout}");
+    assert!(stderr.contains("could not fetch 2024-07-21"), "{stderr}");
+    assert!(stderr.contains("1 of 2 days could not be fetched"));
+}
+
+#[test]
+fn test_search_and_case_sensitive() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        11,
+        28,
+        r#"{"events":[
+            {"year":1989,"text":"Turkey joins a treaty."},
+            {"year":1863,"text":"A turkey is pardoned."},
+            {"year":1905,"text":"Sinn Féin is founded."}
+        ]}"#,
+    );
+    let search = |args: &[&str]| {
+        let output = wiki
+            .cmd()
+            .args(["history", "--silent", "--plain", "-m", "11", "-d", "28"])
+            .args(args)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    assert_eq!(
+        search(&["--search", "TURKEY"]),
+        "1863\tA turkey is pardoned.\n1989\tTurkey joins a treaty.\n",
+    );
+    assert_eq!(
+        search(&["--search", "Turkey", "--case-sensitive"]),
+        "1989\tTurkey joins a treaty.\n",
+    );
+    assert_eq!(search(&["--search", "moon"]), "");
+    assert_eq!(
+        search(&["--search", "turkey", "--reverse"]),
+        "1989\tTurkey joins a treaty.\n1863\tA turkey is pardoned.\n",
+    );
+}
+
+#[test]
+fn test_holiday_filter() {
+    let mut wiki = MockWiki::new();
+    let holidays = r#"[
+        {"text":"Feast of Saint Margaret"},
+        {"text":"Independence Day (Colombia)"},
+        {"text":"Moon Day"}
+    ]"#;
+    wiki.feed("holidays", 7, 20, &format!(r#"{{"holidays":{holidays}}}"#));
+    wiki.feed(
+        "all",
+        7,
+        20,
+        &format!(
+            r#"{{"events":[{{"year":1969,"text":"Apollo 11 lands."}}],
+                "holidays":{holidays}}}"#
+        ),
+    );
+    let history = |args: &[&str]| {
+        let mut cmd = wiki.cmd();
+        cmd.args(["history", "--silent", "-m", "7", "-d", "20"])
+            .args(args);
+        cmd
+    };
+
+    history(&["-t", "holidays", "--holiday-filter", "DAY"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Independence Day"))
+        .stdout(predicate::str::contains("Moon Day"))
+        .stdout(predicate::str::contains("Feast").not());
+    history(&["-t", "holidays", "--holiday-filter", "Yule"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No holidays found for this day."));
+    history(&["-t", "all", "--holiday-filter", "feast"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Apollo 11 lands."))
+        .stdout(predicate::str::contains("Feast of Saint Margaret"))
+        .stdout(predicate::str::contains("Moon Day").not());
+    history(&["--holiday-filter", "feast"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only applies to --type holidays"));
+}
+
+#[test]
+fn test_min_length() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[
+            {"year":1969,
+             "text":"Apollo 11 puts the first people on the Moon's surface."},
+            {"year":1881,"text":"Sitting Bull surrenders."}
+        ]}"#,
+    );
+    let output = wiki
+        .cmd()
+        .args(["history", "--silent", "--plain", "-m", "7", "-d", "20"])
+        .args(["--min-length", "50"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "1969\tApollo 11 puts the first people on the Moon's surface.\n",
+    );
+}
+
+#[test]
+fn test_single_year() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[
+            {"year":1969,"text":"Apollo 11 lands on the Moon."},
+            {"year":1881,"text":"Sitting Bull surrenders."},
+            {"year":1969,"text":"Neil Armstrong walks on the Moon."}
+        ]}"#,
+    );
+    wiki.feed(
+        "all",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands."}],
+            "births":[
+                {"year":1969,"text":"Someone is born."},
+                {"year":1919,"text":"Edmund Hillary is born."}
+            ],
+            "deaths":[{"year":1973,"text":"Bruce Lee dies."}]}"#,
+    );
+    let history = |args: &[&str]| {
+        let mut cmd = wiki.cmd();
+        cmd.args(["history", "--silent", "-m", "7", "-d", "20"])
+            .args(args);
+        cmd
+    };
+
+    let output = history(&["--plain", "--year", "1969"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "1969\tNeil Armstrong walks on the Moon.\n\
+         1969\tApollo 11 lands on the Moon.\n",
+    );
+    history(&["--year", "1900"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "No events recorded for 07-20 in 1900.",
+        ));
+    history(&["--type", "all", "--year", "1969"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Someone is born."))
+        .stdout(predicate::str::contains("Hillary").not())
+        .stdout(predicate::str::contains("No events recorded for 07-20"));
+    history(&["--year", "1969", "--year-from", "1900"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_count_by_type() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "all",
+        7,
+        20,
+        r#"{"events":[
+                {"year":1969,"text":"A"},{"year":1976,"text":"B"},
+                {"year":1881,"text":"C"},{"year":1944,"text":"D"}
+            ],
+            "births":[
+                {"year":1919,"text":"E"},{"year":1938,"text":"F"}
+            ],
+            "deaths":[{"year":1973,"text":"G"}],
+            "holidays":[]}"#,
+    );
+    let output = wiki
+        .cmd()
+        .args(["history", "--silent", "-m", "7", "-d", "20"])
+        .arg("--count-by-type")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    // 30 columns for the biggest count, the rest in proportion
+    let filled = |label: &str| {
+        let line = stdout.lines().find(|line| line.starts_with(label));
+        line.unwrap().matches('█').count()
+    };
+    assert_eq!(filled("Events"), 30, "{stdout}");
+    assert_eq!(filled("Births"), 15, "{stdout}");
+    assert_eq!(filled("Deaths"), 8, "{stdout}"); // 7.5 rounded
+    assert_eq!(filled("Holidays"), 0, "{stdout}");
+    assert!(stdout.lines().any(|line| line.ends_with(" 4")), "{stdout}");
+    assert!(stdout.contains("\x1b[32m"), "default theme bars are green");
+
+    // The bars follow the theme, and lose their colour with it
+    for flags in [&["--theme", "solarized"][..], &["--no-color"]] {
+        let output = wiki
+            .cmd()
+            .args(["history", "--silent", "-m", "7", "-d", "20"])
+            .arg("--count-by-type")
+            .args(flags)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let stdout = String::from_utf8(output).unwrap();
+        assert!(!stdout.contains("\x1b[32m"), "{flags:?}: {stdout:?}");
+        if flags == ["--no-color"] {
+            assert!(!stdout.contains('\x1b'), "{stdout:?}");
+            assert!(stdout.contains(&"█".repeat(30)), "{stdout}");
+        }
+    }
+
+    wiki.cmd()
+        .args(["history", "--count-by-type", "-t", "births"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_empty_feed_gets_a_note() {
+    let mut wiki = MockWiki::new();
+    wiki.feed("events", 7, 20, "{}");
+    let args = ["history", "--quiet", "-m", "7", "-d", "20"];
+
+    wiki.cmd()
+        .args(args)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No entries of this type"))
+        .stdout(predicate::str::contains(
+            "sent an empty feed for this day; another --language",
+        ))
+        .stderr(predicate::str::contains("feed itself is empty").not());
+    wiki.cmd()
+        .args(args)
+        .arg("-v")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "/events/7/20: the feed itself is empty",
+        ));
+}
+
+#[test]
+fn test_sort_and_reverse_holidays() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "holidays",
+        7,
+        20,
+        r#"{"holidays":[
+            {"text":"Moon Day"},
+            {"text":"Independence Day (Colombia)"},
+            {"text":"chess Day"}
+        ]}"#,
+    );
+    let args = ["history", "--silent", "-t", "holidays", "-m", "7", "-d", "20"];
+    let plain = |sort: &[&str]| {
+        let output = wiki
+            .cmd()
+            .args(args)
+            .arg("--plain")
+            .args(sort)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    assert_eq!(
+        plain(&[]),
+        "Moon Day\nIndependence Day (Colombia)\nchess Day\n",
+    );
+    assert_eq!(
+        plain(&["--sort", "text"]),
+        "chess Day\nIndependence Day (Colombia)\nMoon Day\n",
+    );
+
+    assert_eq!(
+        plain(&["--sort", "text", "--reverse"]),
+        "Moon Day\nIndependence Day (Colombia)\nchess Day\n",
+    );
+    assert_eq!(
+        plain(&["--reverse"]),
+        "chess Day\nIndependence Day (Colombia)\nMoon Day\n",
+    );
+    assert_eq!(
+        plain(&["--sort", "text", "--limit", "2"]),
+        "chess Day\nIndependence Day (Colombia)\n",
+    );
+
+    wiki.cmd()
+        .args(args)
+        .args(["--sort", "year"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("holidays have no year"));
+    wiki.cmd()
+        .args(["history", "-t", "events", "--sort", "text"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only applies to --type holidays"));
+}
+
+#[test]
+fn test_sort_year() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[
+            {"year":1881,"text":"Sitting Bull surrenders."},
+            {"year":1969,"text":"Apollo 11 lands."},
+            {"year":1402,"text":"Battle of Ankara."}
+        ]}"#,
+    );
+    let plain = |sort: &[&str]| {
+        let output = wiki
+            .cmd()
+            .args(["history", "--silent", "--plain", "-m", "7", "-d", "20"])
+            .args(sort)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    assert_eq!(
+        plain(&["--sort", "year"]),
+        "1402\tBattle of Ankara.\n1881\tSitting Bull surrenders.\n\
+         1969\tApollo 11 lands.\n",
+    );
+    assert_eq!(
+        plain(&["--sort", "year", "--reverse"]),
+        "1969\tApollo 11 lands.\n1881\tSitting Bull surrenders.\n\
+         1402\tBattle of Ankara.\n",
+    );
+    assert_eq!(
+        plain(&["--sort", "year", "--reverse", "--limit", "2"]),
+        "1969\tApollo 11 lands.\n1881\tSitting Bull surrenders.\n",
+    );
+    assert_eq!(plain(&["--limit", "1"]), "1402\tBattle of Ankara.\n");
+}
+
+#[test]
+fn test_numbered_rows() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[
+            {"year":1969,"text":"Apollo 11 lands."},
+            {"year":356,"text":"Alexander the Great is born."}
+        ]}"#,
+    );
+    let output = wiki
+        .cmd()
+        .args(["history", "--silent", "--numbered", "-m", "7", "-d", "20"])
+        .args(["--preset", "ascii"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert!(stdout.contains("| # | Year | Event"), "{stdout}");
+    assert!(stdout.contains("| 1 |  356 | Alexander"), "{stdout}");
+    assert!(stdout.contains("| 2 | 1969 | Apollo"), "{stdout}");
+}
+
+#[test]
+fn test_group_by_century() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[
+            {"year":1976,"text":"Viking 1 lands on Mars."},
+            {"year":1969,"text":"Apollo 11 lands."},
+            {"year":1881,"text":"Sitting Bull surrenders."}
+        ]}"#,
+    );
+    let run = |extra: &[&str]| {
+        let output = wiki
+            .cmd()
+            .args(["history", "--silent", "--group-by-century"])
+            .args(["-m", "7", "-d", "20", "--preset", "ascii"])
+            .args(["--theme", "mono"])
+            .args(extra)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    let stdout = run(&[]);
+    let headers: Vec<&str> =
+        stdout.lines().filter(|line| line.ends_with("century")).collect();
+    assert_eq!(headers, ["19th century", "20th century"], "{stdout}");
+    let apollo = stdout.find("Apollo").unwrap();
+    assert!(stdout.find("Sitting Bull").unwrap() < apollo, "{stdout}");
+    assert!(apollo < stdout.find("Viking").unwrap(), "{stdout}");
+
+    let stdout = run(&["--reverse"]);
+    let headers: Vec<&str> =
+        stdout.lines().filter(|line| line.ends_with("century")).collect();
+    assert_eq!(headers, ["20th century", "19th century"], "{stdout}");
+
+    wiki.cmd()
+        .args(["history", "--group-by-century", "-t", "holidays"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("holidays have no year"));
+}
+
+#[test]
+fn test_histogram_follows_the_theme() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[
+            {"year":1976,"text":"Viking 1 lands on Mars."},
+            {"year":1969,"text":"Apollo 11 lands."},
+            {"year":1881,"text":"Sitting Bull surrenders."}
+        ]}"#,
+    );
+    let run = |flags: &[&str]| {
+        let output = wiki
+            .cmd()
+            .args(["history", "--silent", "--histogram", "-m", "7", "-d", "20"])
+            .args(flags)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    assert!(run(&[]).contains("\x1b[32m"), "default theme bars are green");
+    assert!(run(&["--theme", "high-contrast"]).contains("\x1b[37m"));
+    for flags in [&["--theme", "mono"][..], &["--no-color"]] {
+        let stdout = run(flags);
+        assert!(!stdout.contains('\x1b'), "{flags:?}: {stdout:?}");
+        assert!(stdout.contains(&"█".repeat(30)), "{stdout}");
+    }
+}
+
+#[test]
+fn test_plain_output_and_separator() {
+    let mut wiki = MockWiki::new();
+    let body = r#"{
+        "events":[
+            {"year":1969,"text":"Apollo 11 lands."},
+            {"year":356,"text":"Alexander the Great is born."}
+        ],
+        "holidays":[{"text":"Moon Day"}]
+    }"#;
+    for feed in ["all", "events", "holidays"] {
+        wiki.feed(feed, 7, 20, body);
+    }
+    let run = |args: &[&str]| {
+        let output = wiki
+            .cmd()
+            .args(["history", "--silent", "--plain", "-m", "7", "-d", "20"])
+            .args(args)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    assert_eq!(
+        run(&["-t", "events"]),
+        "356\tAlexander the Great is born.\n1969\tApollo 11 lands.\n",
+    );
+    assert_eq!(
+        run(&["-t", "events", "--separator", "\\0"]),
+        "356\0Alexander the Great is born.\n1969\0Apollo 11 lands.\n",
+    );
+    assert_eq!(run(&["-t", "holidays", "--separator", ","]), "Moon Day\n");
+
+    let all = run(&["-t", "all", "--separator", "|"]);
+    assert!(all.contains("events|1969|Apollo 11 lands.\n"), "{all}");
+    assert!(all.contains("holidays|Moon Day\n"), "{all}");
+
+    wiki.cmd()
+        .args(["history", "--separator", ","])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--plain"));
+}
+
+#[test]
+#[cfg(not(feature = "clipboard"))]
+fn test_copy_needs_the_clipboard_feature() {
+    let wiki = MockWiki::new();
+    wiki.cmd()
+        .args(["history", "--copy", "-m", "7", "-d", "20"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--features clipboard"));
+}
+
+#[test]
+#[cfg(feature = "clipboard")]
+#[ignore = "needs a display server to own the clipboard"]
+fn test_copy_to_clipboard() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands."}]}"#,
+    );
+    wiki.cmd()
+        .args(["history", "--quiet", "--copy", "-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Copied to clipboard"));
+}
+
+#[test]
+fn test_type_list() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "all",
+        7,
+        20,
+        r#"{
+            "events":[{"year":1969,"text":"Apollo 11 lands."}],
+            "births":[{"year":1304,"text":"Petrarch is born."}],
+            "deaths":[{"year":1973,"text":"Bruce Lee dies."}],
+            "holidays":[{"text":"Moon Day"}]
+        }"#,
+    );
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands."}]}"#,
+    );
+    let run = |types: &str| {
+        let output = wiki
+            .cmd()
+            .args(["history", "--silent", "--plain", "-m", "7", "-d", "20"])
+            .args(["-t", types])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    assert_eq!(
+        run("births,holidays,births"),
+        "births\t1304\tPetrarch is born.\nholidays\tMoon Day\n",
+    );
+    assert_eq!(
+        run("holidays,events"),
+        "holidays\tMoon Day\nevents\t1969\tApollo 11 lands.\n",
+    );
+    // A repeated single type is still the single-type view
+    assert_eq!(run("events,events"), "1969\tApollo 11 lands.\n");
+
+    for (types, error) in [
+        ("events,", "a value is required"),
+        ("events,nope", "invalid value"),
+        ("events,featured", "can't be combined"),
+    ] {
+        wiki.cmd()
+            .args(["history", "-t", types])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(error));
+    }
+
+    // Options tied to one section go by the sections picked
+    let check = |args: &[&str]| {
+        let mut cmd = wiki.cmd();
+        cmd.args(["history", "--silent", "--plain", "-m", "7", "-d", "20"])
+            .args(args);
+        cmd.assert()
+    };
+    check(&["-t", "events,births", "--show-age"])
+        .failure()
+        .stderr(predicate::str::contains("only applies to --type deaths"));
+    check(&["-t", "events,deaths", "--show-age"]).success();
+    check(&["-t", "holidays,events", "--sort", "text"])
+        .success()
+        .stdout(predicate::str::contains("holidays\tMoon Day"));
+    check(&["-t", "events,births", "--sort", "text"])
+        .failure()
+        .stderr(predicate::str::contains("only applies to --type holidays"));
+    check(&["-t", "holidays,events", "--sort", "year"]).success();
+    wiki.cmd()
+        .args(["history", "-t", "holidays", "--group-by-century"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("holidays have no year"));
+    wiki.cmd()
+        .args(["history", "-t", "holidays,events", "--group-by-century"])
+        .args(["--silent", "--theme", "mono", "-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("20th century"))
+        .stdout(predicate::str::contains("Moon Day"));
+}
+
+#[test]
+fn test_dry_run_sends_nothing() {
+    // No feeds are mocked: any request would fail the run
+    let wiki = MockWiki::new();
+    let output = wiki
+        .cmd()
+        .args(["history", "--dry-run", "-m", "7", "-d", "20"])
+        .args(["-t", "anniversaries", "--language", "de"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert!(stdout.contains("Language : de\n"), "{stdout}");
+    assert!(stdout.contains("Type     : anniversaries (feed: all)"));
+    assert!(stdout.contains("/api/rest_v1/feed/onthisday/all/7/20\n"));
+    assert!(stdout.contains("de-all-07-20.json"), "{stdout}");
+    assert!(!stdout.contains("Finished in"), "{stdout}");
+
+    wiki.cmd()
+        .args(["history", "--dry-run", "-m", "7", "-d", "20"])
+        .args(["-t", "events,births"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Type     : events, births (feed: all)\n",
+        ));
+}
+
+#[test]
+fn test_no_header() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands."}]}"#,
+    );
+    let args = ["history", "--quiet", "-m", "7", "-d", "20"];
+
+    wiki.cmd()
+        .args(args)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("On This Day"));
+    wiki.cmd()
+        .args(args)
+        .arg("--no-header")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("On This Day").not())
+        .stdout(predicate::str::contains("Apollo 11 lands."))
+        .stdout(predicate::str::contains("Finished in"));
+    wiki.cmd()
+        .args(args)
+        .args(["--no-header", "--no-timing", "--plain"])
+        .assert()
+        .success()
+        .stdout("1969\tApollo 11 lands.\n");
+}
+
+#[test]
+fn test_no_pager_writes_straight_to_stdout() {
+    let mut wiki = MockWiki::new();
+    let events: Vec<String> = (1900..1990)
+        .map(|year| format!(r#"{{"year":{year},"text":"Event of {year}"}}"#))
+        .collect();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        &format!(r#"{{"events":[{}]}}"#, events.join(",")),
+    );
+
+    // A pager that would swallow the output if it were ever started
+    for flags in [&["--no-pager"][..], &[]] {
+        wiki.cmd()
+            .env("PAGER", "false")
+            .args(["history", "--quiet", "-m", "7", "-d", "20"])
+            .args(flags)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Event of 1900"))
+            .stdout(predicate::str::contains("Event of 1989"))
+            .stdout(predicate::function(|out: &str| {
+                out.matches("On This Day").count() == 1
+            }));
+    }
+}
+
+#[test]
+fn test_hide_empty_sections() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "all",
+        5,
+        4,
+        r#"{"events": [{"year":1970,"text":"Kent State shootings"}]}"#,
+    );
+    wiki.feed("all", 5, 5, "{}");
+    let run = |day: &str| {
+        let output = wiki
+            .cmd()
+            .args(["history", "--quiet", "-t", "all", "--hide-empty"])
+            .args(["-m", "5", "-d", day])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    let sparse = run("4");
+    assert!(sparse.contains("Events") && sparse.contains("Kent State"));
+    for title in ["Births", "Deaths", "Holidays"] {
+        assert!(!sparse.contains(title), "{title} shown:\n{sparse}");
+    }
+    assert!(!sparse.contains("No entries"));
+
+    let empty = run("5");
+    assert!(empty.contains("Nothing recorded for this day."));
+    assert!(!empty.contains('┌'));
+}
+
+#[test]
+fn test_dedupe_collapses_repeated_texts() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        5,
+        4,
+        r#"{"events": [
+            {"year":1970,"text":"Kent State shootings"},
+            {"year":1970,"text":"  kent state SHOOTINGS "},
+            {"year":1979,"text":"Thatcher takes office"}
+        ]}"#,
+    );
+    let output = wiki
+        .cmd()
+        .args(["history", "--quiet", "-m", "5", "-d", "4", "--dedupe"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 1 duplicate entry."))
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+    assert_eq!(stdout.to_lowercase().matches("kent state").count(), 1);
+    assert!(stdout.contains("Kent State shootings"));
+    assert!(stdout.contains("Thatcher"));
+}
+
+#[test]
+fn test_silent_prints_only_the_table() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands on the Moon."}]}"#,
+    );
+
+    let output = wiki
+        .cmd()
+        .args(["history", "--silent", "-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.starts_with('┌'), "unexpected output:\n{stdout}");
+    assert!(stdout.contains("Apollo 11"));
+    assert!(!stdout.contains("On This Day"));
+    assert!(!stdout.contains("Finished in"));
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_unreachable_host_message() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let unreachable = || {
+        let mut cmd = Command::cargo_bin("time_cli").unwrap();
+        cmd.env("TEST_WIKIPEDIA_API_URL", "http://127.0.0.1:1")
+            .env("TIME_CLI_CACHE_DIR", cache_dir.path())
+            .args(["history", "--quiet"]);
+        cmd
+    };
+
+    unreachable()
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Could not reach 127.0.0.1 — check your connection \
+             (or use --offline)",
+        ))
+        .stderr(predicate::str::contains("Caused by").not());
+
+    unreachable()
+        .arg("-v")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Could not reach 127.0.0.1"))
+        .stderr(predicate::str::contains("Caused by"));
+}
+
+#[test]
+fn test_range_reports_failed_days_and_keeps_the_rest() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        3,
+        1,
+        r#"{"events":[{"year":1901,"text":"Good day"}]}"#,
+    );
+    wiki.server
+        .mock("GET", "/api/rest_v1/feed/onthisday/events/3/2")
+        .with_status(500)
+        .create();
+
+    let output = wiki
+        .cmd()
+        .args(["history", "--quiet", "--range", "2024-03-01..2024-03-02"])
+        .assert()
+        .failure()
+        .get_output()
+        .clone();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(stdout.contains("Good day"));
+    assert!(stderr.contains("could not fetch 2024-03-02"), "{stderr}");
+    assert!(stderr.contains("1 of 2 days could not be fetched"));
+}
+
+#[test]
+fn test_type_anniversaries_merges_events_and_holidays() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "all",
+        5,
+        4,
+        r#"{
+            "events": [{"year":1970,"text":"Kent State shootings"}],
+            "births": [{"year":1929,"text":"Audrey Hepburn"}],
+            "holidays": [{"text":"Star Wars Day"}]
+        }"#,
+    );
+
+    let output = wiki
+        .cmd()
+        .args([
+            "history", "--quiet", "-t", "anniversaries", "-m", "5", "-d", "4",
+        ])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert_eq!(stdout.matches('┌').count(), 1, "expected one table");
+    let events_at = stdout.find("Kent State").unwrap();
+    let holiday_at = stdout.find("Star Wars Day").unwrap();
+    assert!(events_at < holiday_at);
+    assert!(!stdout.contains("Hepburn"));
+}
+
+#[test]
+fn test_compact_statistics() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["--statistics", "--compact"]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert!(stdout.lines().count() < 4, "too long:\n{stdout}");
+    assert!(stdout.contains("Week") && !stdout.contains('─'));
+}
+
+#[test]
+fn test_date_fmt() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["--date-fmt", "%Y|%j"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^\d{4}\|\d{3}\n$").unwrap());
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["add", "2024-01-31", "1mo", "--date-fmt", "%d.%m.%Y"]);
+    cmd.assert().success().stdout("29.02.2024\n");
+
+    // Global, and ahead of the other clock options
+    let clock = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("time_cli").unwrap();
+        cmd.env("TIME_CLI_NOW", "2024-03-01T12:00:00Z")
+            .args(["--utc", "--date-fmt", "%H:%M:%S%.3f"])
+            .args(args);
+        cmd
+    };
+    clock(&["--progress-precision", "2"])
+        .assert()
+        .success()
+        .stdout("12:00:00.000\n");
+    clock(&["now", "--time-format", "iso"])
+        .assert()
+        .success()
+        .stdout("12:00:00.000\n");
+    clock(&["sub", "2024-03-01", "1d", "--format", "%F"])
+        .assert()
+        .success()
+        .stdout("00:00:00.000\n");
+
+    clock(&["--statistics"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("can't go with --statistics"));
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["now", "--date-fmt", "%Q"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("'%Q' is not a valid"));
+}
+
+#[test]
+fn test_format_presets() {
+    let clock = |format: &str| {
+        let mut cmd = Command::cargo_bin("time_cli").unwrap();
+        cmd.env("TIME_CLI_NOW", "2024-03-01T12:00:00Z")
+            .args(["--utc", "--format", format]);
+        cmd
+    };
+
+    clock("human")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Friday, March 01, 2024 12:00:00 PM"));
+    clock("iso").assert().success().stdout("2024-03-01T12:00:00+00:00\n");
+    clock("rfc2822")
+        .assert()
+        .success()
+        .stdout("Fri, 1 Mar 2024 12:00:00 +0000\n");
+    clock("unix").assert().success().stdout("1709294400\n");
+    clock("short").assert().success().stdout("2024-03-01 12:00\n");
+    clock("%d/%m").assert().success().stdout("01/03\n");
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.env("TIME_CLI_NOW", "2024-03-01T12:00:00Z")
+        .args(["now", "--utc", "--time-format", "ISO"]);
+    cmd.assert().success().stdout("2024-03-01T12:00:00+00:00\n");
+}
+
+#[test]
+fn test_watch_interval() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["--watch", "--interval", "100", "--format", "unix"])
+        .timeout(std::time::Duration::from_millis(600));
+    let output = cmd.assert().interrupted().get_output().stdout.clone();
+    let redraws = String::from_utf8(output).unwrap().matches("\x1b[2J").count();
+    assert!(redraws >= 2, "only {redraws} redraws");
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["--watch", "--interval", "50"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("50 is not in 100.."));
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["--interval", "500"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--watch"));
+}
+
+#[test]
+fn test_timezone_flags() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["--utc", "--format", "%z"]);
+    cmd.assert().success().stdout("+0000\n");
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["--timezone", "Asia/Kolkata", "--format", "%:z"]);
+    cmd.assert().success().stdout("+05:30\n");
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["history", "--timezone", "Nowhere/Special"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("not a known IANA time zone"));
+}
+
+#[test]
+fn test_format_rejects_bad_specifier() {
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.args(["--format", "%Y %Q"]);
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("'%Q' is not a valid strftime"));
+}
+
+#[test]
+fn test_show_source_footer() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands on the Moon."}]}"#,
+    );
+    let url = format!(
+        "{}/api/rest_v1/feed/onthisday/events/7/20",
+        wiki.server.url(),
+    );
+
+    wiki.cmd()
+        .args(["history", "--show-source", "-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Source: {url} (fetched ")))
+        .stdout(predicate::str::contains(" UTC)"));
+}
+
+#[test]
+fn test_no_timing() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands on the Moon."}]}"#,
+    );
+
+    wiki.cmd()
+        .args(["history", "--quiet", "-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Finished in"));
+    wiki.cmd()
+        .args(["history", "--quiet", "--no-timing", "-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Apollo 11"))
+        .stdout(predicate::str::contains("Finished in").not());
+}
+
+#[test]
+fn test_theme_mono_has_no_escapes() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands on the Moon."}]}"#,
+    );
+    let run = |theme: &str| {
+        let output = wiki
+            .cmd()
+            .args(["history", "--theme", theme, "-m", "7", "-d", "20"])
+            .assert()
+            .success()
+            .get_output()
+            .clone();
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert!(run("default").contains('\x1b'));
+    let mono = run("mono");
+    assert!(mono.contains("Apollo 11"));
+    assert!(!mono.contains('\x1b'), "escapes in mono output:\n{mono:?}");
+
+    for value in ["1", "0", "false"] {
+        wiki.cmd()
+            .env("NO_COLOR", value)
+            .args(["history", "--theme", "solarized", "-m", "7", "-d", "20"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains('\x1b').not());
+    }
+    // Set but empty doesn't count
+    wiki.cmd()
+        .env("NO_COLOR", "")
+        .args(["history", "--theme", "solarized", "-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains('\x1b'));
+}
+
+#[test]
+fn test_no_color_every_command() {
+    let mut wiki = MockWiki::new();
+    wiki.server
+        .mock(
+            "GET",
+            mockito::Matcher::Regex(
+                r"^/api/rest_v1/feed/onthisday/events/\d+/\d+$".into(),
+            ),
+        )
+        .with_body(r#"{"events":[{"year":1969,"text":"Apollo 11"}]}"#)
+        .create();
+    let statistics = [
+        "--statistics",
+        "--detailed",
+        "--explain",
+        "--lat",
+        "52.52",
+        "--lon",
+        "13.40",
+    ];
+    let run = |args: &[&str], no_color: Option<&str>| {
+        let mut cmd = wiki.cmd();
+        cmd.env("TIME_CLI_NOW", "2024-03-01T12:00:00Z").args(args);
+        if let Some(value) = no_color {
+            cmd.env("NO_COLOR", value);
+        }
+        let output = cmd.assert().success().get_output().clone();
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    for args in [&statistics[..], &["today"]] {
+        assert!(run(args, None).contains("\x1b["), "no colour: {args:?}");
+    }
+    let no_color_flag = [&statistics[..], &["--no-color"]].concat();
+    for (args, no_color) in [
+        (&statistics[..], Some("1")),
+        (&no_color_flag[..], None),
+        (&["today"], Some("1")),
+        (&["today", "--no-color"], None),
+        (&[], Some("1")),
+    ] {
+        let stdout = run(args, no_color);
+        assert!(!stdout.contains("\x1b["), "{args:?}:\n{stdout:?}");
+    }
+}
+
+#[test]
+fn test_fallback_language() {
+    let mut wiki = MockWiki::new();
+    wiki.feed_in("ht", "events", 7, 20, r#"{"events":[]}"#);
+    wiki.feed_in(
+        "es",
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"El Apolo 11 llega a la Luna."}]}"#,
+    );
+
+    wiki.cmd()
+        .env("TEST_WIKIPEDIA_API_URL", wiki.per_lang_url())
+        .args(["history", "-l", "ht", "--fallback", "es,en"])
+        .args(["-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Apolo 11"))
+        .stdout(predicate::str::contains(
+            "(from es.wikipedia.org; nothing found in 'ht')",
+        ));
+}
+
+#[test]
+fn test_fallback_from_three_letter_language() {
+    let mut wiki = MockWiki::new();
+    wiki.feed_in("nah", "events", 7, 20, r#"{"events":[]}"#);
+    wiki.feed_in(
+        "zh-yue",
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands."}]}"#,
+    );
+
+    wiki.cmd()
+        .env("TEST_WIKIPEDIA_API_URL", wiki.per_lang_url())
+        .args(["history", "-l", "nah", "--fallback", "zh-yue,en"])
+        .args(["-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Apollo 11 lands."))
+        .stdout(predicate::str::contains(
+            "(from zh-yue.wikipedia.org; nothing found in 'nah')",
+        ));
+}
+
+#[test]
+fn test_feed_links_the_fallback_wiki() {
+    let mut wiki = MockWiki::new();
+    wiki.feed_in("nah", "events", 7, 20, r#"{"events":[]}"#);
+    wiki.feed_in(
+        "zh-yue",
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands."}]}"#,
+    );
+    let feed = |format: &str| {
+        wiki.cmd()
+            .env("TEST_WIKIPEDIA_API_URL", wiki.per_lang_url())
+            .args(["history", "--quiet", "-l", "nah", "--fallback", "zh-yue"])
+            .args(["-m", "7", "-d", "20", "--feed", format])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone()
+    };
+
+    let channel = rss::Channel::read_from(&feed("rss")[..]).unwrap();
+    assert_eq!(channel.link(), "https://zh-yue.wikipedia.org/");
+    let atom = atom_syndication::Feed::read_from(&feed("atom")[..]).unwrap();
+    assert_eq!(atom.id(), "https://zh-yue.wikipedia.org/");
+    let id = atom.entries()[0].id();
+    assert!(id.starts_with("https://zh-yue.wikipedia.org/#07-20"), "{id}");
+}
+
+#[test]
+fn test_offline_uses_only_the_disk_cache() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands on the Moon."}]}"#,
+    );
+    wiki.cmd()
+        .args(["history", "--quiet", "-m", "7", "-d", "20"])
+        .assert()
+        .success();
+
+    let offline = |day: &str| {
+        let mut cmd = wiki.cmd();
+        cmd.env("TEST_WIKIPEDIA_API_URL", "http://127.0.0.1:1")
+            .args(["history", "--quiet", "--offline", "-m", "7", "-d", day]);
+        cmd
+    };
+    offline("20")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Apollo 11"));
+    offline("21")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No cached copy of"))
+        .stderr(predicate::str::contains("Could not reach").not());
+}
+
+#[test]
+fn test_cache_ttl_zero_always_refetches() {
+    let mut wiki = MockWiki::new();
+    let body =
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands on the Moon."}]}"#;
+    let mock = wiki.feed("events", 7, 20, body).expect(2);
+
+    for _ in 0..2 {
+        wiki.cmd()
+            .args(["history", "--quiet", "--cache-ttl", "0"])
+            .args(["-m", "7", "-d", "20"])
+            .assert()
+            .success();
+    }
+    mock.assert();
+}
+
+#[test]
+fn test_refresh_bypasses_and_rewrites_the_cache() {
+    let mut wiki = MockWiki::new();
+    let body =
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands on the Moon."}]}"#;
+    let mock = wiki.feed("events", 7, 20, body).expect(2);
+
+    // Cached, refreshed past the fresh copy, then served from the rewrite
+    for refresh in [false, true, false] {
+        let mut cmd = wiki.cmd();
+        cmd.args(["history", "--quiet", "-m", "7", "-d", "20"]);
+        if refresh {
+            cmd.arg("--refresh");
+        }
+        cmd.assert().success();
+    }
+    mock.assert();
+}
+
+#[test]
+fn test_verbose_logs_size_and_latency() {
+    let mut wiki = MockWiki::new();
+    let body =
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands on the Moon."}]}"#;
+    wiki.feed("events", 7, 20, body);
+
+    wiki.cmd()
+        .args(["history", "--quiet", "-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("bytes").not());
+    wiki.cmd()
+        .args(["-v", "history", "--quiet", "--refresh", "-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stderr(
+            predicate::str::is_match(format!(
+                r"200 OK \S+/events/7/20: {} bytes in \d+ ms",
+                body.len(),
+            ))
+            .unwrap(),
+        );
+}
+
+#[test]
+fn test_verbose_run_summary() {
+    let mut wiki = MockWiki::new();
+    let body = r#"{"events":[
+        {"year":1969,"text":"Apollo 11 lands."},
+        {"year":1881,"text":"Sitting Bull surrenders."}
+    ]}"#;
+    wiki.feed("events", 7, 20, body);
+    let args = ["-v", "history", "--quiet", "-m", "7", "-d", "20"];
+
+    wiki.cmd()
+        .args(args)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "days 1, cache hits 0, misses 1, bytes received {}",
+            body.len(),
+        )))
+        .stderr(predicate::str::contains("Entries: events 2, births 0"));
+    wiki.cmd()
+        .args(args)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "days 1, cache hits 1, misses 0, bytes received 0",
+        ));
+    wiki.cmd()
+        .args(["history", "--quiet", "-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Summary").not());
+}
+
+#[test]
+fn test_stale_ok_falls_back_to_the_cache() {
+    let mut wiki = MockWiki::new();
+    let body =
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands on the Moon."}]}"#;
+    let mock = wiki.feed("events", 7, 20, body);
+    let args = ["history", "--quiet", "--refresh", "-m", "7", "-d", "20"];
+    wiki.cmd().args(args).assert().success();
+
+    // Upstream now fails (unmatched mockito requests get a 501)
+    mock.remove();
+    wiki.cmd().args(args).assert().failure();
+    wiki.cmd()
+        .args(args)
+        .arg("--stale-ok")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Apollo 11 lands on the Moon."))
+        .stderr(predicate::str::contains("showing cached data from 20"));
+}
+
+#[test]
+fn test_headline() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[
+            {"year":2012,"text":"A newer event."},
+            {"year":1969,"text":"Apollo 11 lands on the Moon."}
+        ]}"#,
+    );
+
+    let output = wiki
+        .cmd()
+        .args(["history", "--quiet", "--headline", "-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    let headline = stdout
+        .lines()
+        .find(|line| line.contains("Today in"))
+        .expect("no headline");
+    assert!(headline.contains("Today in 2012: A newer event."));
+    assert!(stdout.contains("Apollo 11"), "table still follows");
+}
+
+#[test]
+fn test_diff_counts_per_category() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "all",
+        7,
+        4,
+        r#"{"events":[{"year":1776,"text":"a"},{"year":1826,"text":"b"}],
+            "holidays":[{"text":"Independence Day"}]}"#,
+    );
+    wiki.feed(
+        "all",
+        7,
+        14,
+        r#"{"events":[{"year":1789,"text":"Storming of the Bastille"}],
+            "births":[{"year":1862,"text":"Gustav Klimt"}],
+            "holidays":[{"text":"Bastille Day"}]}"#,
+    );
+
+    let output = wiki
+        .cmd()
+        .args(["diff", "07-04", "07-14"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+    let row = |title: &str| {
+        stdout
+            .lines()
+            .find(|line| line.contains(title))
+            .unwrap_or_else(|| panic!("no {title} row in:\n{stdout}"))
+            .to_string()
+    };
+
+    assert!(row("Category").contains("July 4"));
+    assert!(row("Category").contains("July 14"));
+    assert!(row("Events").contains("2 ▲") && row("Events").contains("1 ▼"));
+    assert!(row("Births").contains("0 ▼") && row("Births").contains("1 ▲"));
+    assert!(!row("Holidays").contains('▲'));
+    assert!(row("Total").contains("3"));
+
+    wiki.cmd()
+        .args(["diff", "02-30", "07-14"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'02-30' is not a valid day"));
+}
+
+#[test]
+fn test_first_and_last_by_year() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[
+            {"year":1969,"text":"Apollo 11 lands on the Moon."},
+            {"year":1402,"text":"Battle of Ankara."},
+            {"year":2012,"text":"A newer event."}
+        ]}"#,
+    );
+
+    wiki.cmd()
+        .args(["history", "--quiet", "--first", "-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("1402 — Battle of Ankara.\n"));
+    wiki.cmd()
+        .args(["history", "--quiet", "--last", "-m", "7", "-d", "20"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("2012 — A newer event.\n"));
+    wiki.cmd()
+        .args(["history", "--first", "--last"])
+        .assert()
+        .failure();
+
+    // Holidays have no year: they're picked alphabetically instead
+    wiki.feed(
+        "holidays",
+        7,
+        20,
+        r#"{"holidays":[
+            {"text":"Moon Day"},
+            {"text":"Day of Colombia"},
+            {"text":"zodiac fair"}
+        ]}"#,
+    );
+    let holidays =
+        ["history", "--quiet", "-t", "holidays", "-m", "7", "-d", "20"];
+    wiki.cmd()
+        .args(holidays)
+        .arg("--first")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Day of Colombia\n"));
+    wiki.cmd()
+        .args(holidays)
+        .arg("--last")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("zodiac fair\n"));
+}
+
+#[test]
+fn test_user_agent() {
+    let mut wiki = MockWiki::new();
+    let body = r#"{"events":[{"year":1969,"text":"Apollo 11"}]}"#;
+    let default = wiki
+        .server
+        .mock("GET", "/api/rest_v1/feed/onthisday/events/7/20")
+        .match_header(
+            "user-agent",
+            mockito::Matcher::Regex(
+                r"^time_cli/\S+ \(\+https://github\.com/12Mosch/time_cli\)$"
+                    .into(),
+            ),
+        )
+        .with_body(body)
+        .create();
+    let custom = wiki
+        .server
+        .mock("GET", "/api/rest_v1/feed/onthisday/events/7/21")
+        .match_header("user-agent", "research-bot/2.0 (me@example.org)")
+        .with_body(body)
+        .create();
+
+    wiki.cmd()
+        .args(["history", "--quiet", "-m", "7", "-d", "20"])
+        .assert()
+        .success();
+    wiki.cmd()
+        .env("TIME_CLI_USER_AGENT", "research-bot/2.0 (me@example.org)")
+        .args(["history", "--quiet", "-m", "7", "-d", "21"])
+        .assert()
+        .success();
+
+    default.assert();
+    custom.assert();
+}
+
+#[test]
+fn test_today_dashboard() {
+    let mut wiki = MockWiki::new();
+    wiki.server
+        .mock(
+            "GET",
+            mockito::Matcher::Regex(
+                r"^/api/rest_v1/feed/onthisday/events/\d+/\d+$".into(),
+            ),
+        )
+        .with_body(
+            r#"{"events":[
+                {"year":1969,"text":"Apollo 11 lands on the Moon."},
+                {"year":2012,"text":"A newer event."},
+                {"year":1402,"text":"Battle of Ankara."}
+            ]}"#,
+        )
+        .create();
+
+    let output = wiki
+        .cmd()
+        .args(["today", "--limit", "2"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+    assert!(stdout.contains("The current time is:"));
+    assert!(stdout.contains("Time statistics"));
+    assert!(stdout.contains("On This Day"));
+    assert!(stdout.contains("A newer event.") && stdout.contains("Apollo 11"));
+    assert!(!stdout.contains("Battle of Ankara."), "limit ignored");
+    let newest = stdout.find("A newer event.").unwrap();
+    assert!(newest < stdout.find("Apollo 11").unwrap(), "{stdout}");
+
+    // No network (and nothing cached): the dashboard still prints
+    let empty_cache = tempfile::tempdir().unwrap();
+    wiki.cmd()
+        .env("TEST_WIKIPEDIA_API_URL", "http://127.0.0.1:1")
+        .env("TIME_CLI_CACHE_DIR", empty_cache.path())
+        .arg("today")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("The current time is:"))
+        .stdout(predicate::str::contains("Events unavailable right now"));
+}
+
+#[test]
+fn test_today_follows_the_theme() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        3,
+        1,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands."}]}"#,
+    );
+    let run = |args: &[&str]| {
+        let output = wiki
+            .cmd()
+            .env("TIME_CLI_NOW", "2024-03-01T12:00:00Z")
+            .arg("--utc")
+            .args(args)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    // The history part is `history`'s own output for the day
+    for theme in ["solarized", "default", "mono"] {
+        let today = run(&["today", "--theme", theme]);
+        let history = run(&["history", "--no-timing", "--theme", theme]);
+        assert!(today.ends_with(&history), "{today:?}\n{history:?}");
+    }
+    let mono = run(&["today", "--theme", "mono"]);
+    let history = &mono[mono.find("On This Day").unwrap()..];
+    assert!(history.contains("│ 1969 ┆ Apollo 11 lands. │"), "{mono:?}");
+    assert!(!history.contains('\x1b'), "{mono:?}");
+    let default = run(&["today"]);
+    assert!(default.contains("\x1b[4m\x1b[1m— On This Day"), "{default:?}");
+}
+
+#[test]
+fn test_favorites() {
+    let mut wiki = MockWiki::new();
+    wiki.feed(
+        "events",
+        7,
+        20,
+        r#"{"events":[{"year":1969,"text":"Apollo 11 lands."}]}"#,
+    );
+    let config = tempfile::tempdir().unwrap();
+    let favorites = |args: &[&str]| {
+        let mut cmd = wiki.cmd();
+        cmd.env("TIME_CLI_CONFIG_DIR", config.path())
+            .arg("favorites")
+            .args(args);
+        cmd
+    };
+
+    favorites(&["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No favorites yet"));
+    favorites(&["add", "07-20", "--name", "moon"])
+        .assert()
+        .success()
+        .stdout("Saved 07-20 as 'moon'\n");
+    favorites(&["add", "01-01"])
+        .assert()
+        .success()
+        .stdout("Saved 01-01 as '01-01'\n");
+    favorites(&["add", "12-25", "--name", "xmas"]).assert().success();
+    favorites(&["add", "12-25", "--name", "christmas"]).assert().success();
+    favorites(&["add", "13-01"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a valid day"));
+    favorites(&["list"])
+        .assert()
+        .success()
+        .stdout(
+            "01-01  01-01\n07-20  moon\n12-25  christmas\n12-25  xmas\n",
+        );
+
+    wiki.cmd()
+        .env("TIME_CLI_CONFIG_DIR", config.path())
+        .args(["history", "--silent", "--plain", "--favorite", "moon"])
+        .assert()
+        .success()
+        .stdout("1969\tApollo 11 lands.\n");
+    wiki.cmd()
+        .env("TIME_CLI_CONFIG_DIR", config.path())
+        .args(["history", "--favorite", "mars"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no favorite named 'mars'"));
+
+    favorites(&["remove", "moon"])
+        .assert()
+        .success()
+        .stdout("Removed 'moon' (07-20)\n");
+    favorites(&["remove", "01-01"])
+        .assert()
+        .success()
+        .stdout("Removed '01-01' (01-01)\n");
+    favorites(&["remove", "12-25"])
+        .assert()
+        .success()
+        .stdout("Removed 2 favorites on 12-25\n");
+    favorites(&["remove", "12-25"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no favorites on 12-25"));
+    assert!(!config.path().join("favorites.json.lock").exists());
+}
+
+#[test]
+fn test_favorites_recover_from_a_corrupt_file_and_a_stale_lock() {
+    let config = tempfile::tempdir().unwrap();
+    let file = config.path().join("favorites.json");
+    std::fs::write(&file, "{not json").unwrap();
+    let lock = std::fs::File::create(file.with_extension("json.lock")).unwrap();
+    let an_hour_ago = std::time::SystemTime::now()
+        - std::time::Duration::from_secs(3600);
+    lock.set_modified(an_hour_ago).unwrap();
+
+    let mut cmd = Command::cargo_bin("time_cli").unwrap();
+    cmd.env("TIME_CLI_CONFIG_DIR", config.path())
+        .args(["favorites", "add", "07-20"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("was unreadable; moved it to"));
+    assert_eq!(
+        std::fs::read_to_string(file.with_extension("json.bak")).unwrap(),
+        "{not json",
+    );
+    let saved = std::fs::read_to_string(&file).unwrap();
+    assert!(saved.contains(r#""07-20": "07-20""#), "{saved}");
+}